@@ -0,0 +1,102 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+@group(2) @binding(0)
+var<uniform> saturation: f32;
+@group(2) @binding(1)
+var density_texture: texture_2d<f32>;
+@group(2) @binding(2)
+var density_sampler: sampler;
+
+fn gamma_function(value: f32) -> f32 {
+    if value <= 0.0 {
+        return value;
+    }
+    if value <= 0.04045 {
+        return value / 12.92; // linear falloff in dark values
+    }
+    return pow((value + 0.055) / 1.055, 2.4); // gamma curve in other area
+}
+
+// Polynomial fits of the matplotlib color maps, see https://www.shadertoy.com/view/WlfXRN
+fn polynomial_ramp(
+    t: f32,
+    c0: vec3<f32>,
+    c1: vec3<f32>,
+    c2: vec3<f32>,
+    c3: vec3<f32>,
+    c4: vec3<f32>,
+    c5: vec3<f32>,
+    c6: vec3<f32>,
+) -> vec3<f32> {
+    return c0 + t * (c1 + t * (c2 + t * (c3 + t * (c4 + t * (c5 + t * c6)))));
+}
+
+fn viridis(t: f32) -> vec3<f32> {
+    return polynomial_ramp(
+        t,
+        vec3<f32>(0.2777273272234177, 0.005407344544966578, 0.3340998053353061),
+        vec3<f32>(0.1050930431085774, 1.404613529898575, 1.384590162594685),
+        vec3<f32>(-0.3308618287255563, 0.214847559468213, 0.09509516302823659),
+        vec3<f32>(-4.634230498983486, -5.799100973351585, -19.33244095627987),
+        vec3<f32>(6.228269936347081, 14.17993336680509, 56.69055260068105),
+        vec3<f32>(4.776384997670288, -13.74514537774601, -65.35303263337234),
+        vec3<f32>(-5.435455855934631, 4.645852612178535, 26.3124352495832),
+    );
+}
+
+fn inferno(t: f32) -> vec3<f32> {
+    return polynomial_ramp(
+        t,
+        vec3<f32>(0.0002189403691192265, 0.001651004631001012, -0.01948089843709184),
+        vec3<f32>(0.1065134194856116, 0.5639564367884091, 3.932712388889277),
+        vec3<f32>(11.60249308247187, -3.972853965665698, -15.9423941062914),
+        vec3<f32>(-41.70399613139459, 17.43639888205313, 44.35414519872813),
+        vec3<f32>(77.162935699427, -33.40235894210092, -81.80730925738993),
+        vec3<f32>(-71.31942824499214, 32.62606426397723, 73.20951985803202),
+        vec3<f32>(25.13112622477341, -12.24266895238567, -23.07032500287172),
+    );
+}
+
+fn magma(t: f32) -> vec3<f32> {
+    return polynomial_ramp(
+        t,
+        vec3<f32>(-0.002136485053939582, -0.000749655052795221, -0.005386127855323933),
+        vec3<f32>(0.2516605407371642, 0.6775232436837668, 2.494026599312351),
+        vec3<f32>(8.353717279216625, -3.577719514958484, 0.3144679030132573),
+        vec3<f32>(-27.66873308576866, 14.26473078096533, -13.64921318813922),
+        vec3<f32>(52.17613981234068, -27.94360607168351, 12.94416944238394),
+        vec3<f32>(-50.76852536473588, 29.04658282127291, 4.23415299384598),
+        vec3<f32>(18.65570506591883, -11.48977351997711, -5.601961508734096),
+    );
+}
+
+fn ramp(t: f32) -> vec3<f32> {
+#ifdef RAMP_VIRIDIS
+    return viridis(t);
+#else ifdef RAMP_INFERNO
+    return inferno(t);
+#else ifdef RAMP_MAGMA
+    return magma(t);
+#else
+    return vec3<f32>(t);
+#endif
+}
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    let density = textureSample(density_texture, density_sampler, mesh.uv).r;
+
+#ifdef SCALE_LOG
+    let t = log(1.0 + density) / log(1.0 + saturation);
+#else
+    let t = density / saturation;
+#endif
+
+    let color = clamp(ramp(clamp(t, 0.0, 1.0)), vec3<f32>(0.0), vec3<f32>(1.0));
+    return vec4<f32>(
+        gamma_function(color.r),
+        gamma_function(color.g),
+        gamma_function(color.b),
+        1.0,
+    );
+}
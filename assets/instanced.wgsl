@@ -0,0 +1,280 @@
+#define_import_path primes::instanced
+
+#import bevy_sprite::mesh2d_functions::mesh2d_position_world_to_clip
+
+const PI: f32 = 3.141592653589793;
+
+// Radians per unit of the fixed point turns of `i_turns`, 2^32 per turn.
+const TURNS_TO_RADIANS: f32 = 2.0 * PI / 4294967296.0;
+
+// Layouts, mirroring `Layout` in Rust.
+const LAYOUT_POLAR: u32 = 0u;
+const LAYOUT_CYLINDER: u32 = 1u;
+const LAYOUT_HELIX: u32 = 2u;
+
+// Radius laws, mirroring `RadiusLaw` in Rust.
+const RADIUS_LINEAR: u32 = 0u;
+const RADIUS_SQRT: u32 = 1u;
+const RADIUS_LOG: u32 = 2u;
+const RADIUS_PRIME_OVER_LOG: u32 = 3u;
+
+// Radius of the cylinder layout.
+const CYLINDER_RADIUS: f32 = 20.0;
+
+// Height of one turn of the helix layout.
+const HELIX_PITCH: f32 = 50.0;
+
+// Bits of `i_flags`, mirroring `InstanceData::flags` in Rust.
+const FLAG_HIGHLIGHT: u32 = 1u;
+
+// Scale and color of highlighted primes.
+const HIGHLIGHT_SCALE: f32 = 4.0;
+const HIGHLIGHT_COLOR: vec4<f32> = vec4<f32>(4.0, 4.0, 4.0, 1.0);
+
+struct Globals {
+    elapsed_seconds: f32,
+    zoom: f32,
+    pulse_frequency: f32,
+    phase_factor: f32,
+    rotation_rate: f32,
+    radius_divisor: f32,
+    zoom_base_in: f32,
+    zoom_base_out: f32,
+    view_from_world: mat4x4<f32>,
+    clip_from_view: mat4x4<f32>,
+    current_layout: u32,
+    previous_layout: u32,
+    transition: f32,
+    radius_law: u32,
+    previous_radius_law: u32,
+    // The view center in polar coordinates, see `polar_position`. Its angle includes the rotation.
+    center_turns: u32,
+    center_prime: u32,
+    previous_center_prime: u32,
+    center_prime_fraction: f32,
+    previous_center_prime_fraction: f32,
+    center_radius: f32,
+    center_angle: f32,
+    // The view center in world space, which the positions of the styles are relative to.
+    center: vec2<f32>,
+    // Factor of the size of every point.
+    point_scale: f32,
+}
+
+@group(2) @binding(0)
+var<uniform> globals: Globals;
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+
+    @location(3) i_prime: u32,
+    @location(4) i_turns: u32,
+    @location(5) i_gap: u32,
+    @location(6) i_flags: u32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) uv: vec2<f32>,
+};
+
+fn gamma_function(value: f32) -> f32 {
+    if value <= 0.0 {
+        return value;
+    }
+    if value <= 0.04045 {
+        return value / 12.92; // linear falloff in dark values
+    }
+    return pow((value + 0.055) / 1.055, 2.4); // gamma curve in other area
+}
+
+fn zoom_scale() -> f32 {
+    if globals.zoom > 0.0 {
+        return 1.0 / pow(globals.zoom_base_in, globals.zoom);
+    }
+
+    return 1.0 / pow(globals.zoom_base_out, globals.zoom);
+}
+
+fn radius_law(law: u32, p: f32) -> f32 {
+    switch law {
+        case RADIUS_SQRT: {
+            return 32.0 * sqrt(p);
+        }
+        case RADIUS_LOG: {
+            return 512.0 * log(p);
+        }
+        case RADIUS_PRIME_OVER_LOG: {
+            return p / log(p);
+        }
+        default: {
+            return p;
+        }
+    }
+}
+
+// Distance of a prime from the origin by the radius law, blended by the transition like the layout.
+fn radius(prime: u32) -> f32 {
+    var r = radius_law(globals.radius_law, f32(prime));
+    if globals.transition < 1.0 {
+        r = mix(radius_law(globals.previous_radius_law, f32(prime)), r, globals.transition);
+    }
+    return r / globals.radius_divisor;
+}
+
+// Angle of a prime in radians, `angle_multiplier * prime mod 2π`.
+fn instance_angle(vertex: Vertex) -> f32 {
+    return f32(vertex.i_turns) * TURNS_TO_RADIANS;
+}
+
+fn log1p(x: f32) -> f32 {
+    // log(1 + x) loses the digits of small x
+    if abs(x) < 1e-3 {
+        return x * (1.0 - x * (0.5 - x / 3.0));
+    }
+    return log(1.0 + x);
+}
+
+// The radius law of `prime` minus the one of the center, which lies at the prime `center_prime +
+// center_fraction`. Computed from the difference of the primes, so it stays exact near the center.
+fn radius_law_difference(law: u32, prime: u32, center_prime: u32, center_fraction: f32) -> f32 {
+    let p = f32(prime);
+    let c = f32(center_prime) + center_fraction;
+    if c < 16.0 {
+        // Near the origin the radii themselves are small enough
+        return radius_law(law, p) - globals.center_radius * globals.radius_divisor;
+    }
+
+    var d = p - c;
+    if abs(d) < 16777216.0 {
+        // Exact for the primes near the center, where it is seen
+        d = f32(bitcast<i32>(prime - center_prime)) - center_fraction;
+    }
+    switch law {
+        case RADIUS_SQRT: {
+            return 32.0 * d / (sqrt(p) + sqrt(c));
+        }
+        case RADIUS_LOG: {
+            return 512.0 * log1p(d / c);
+        }
+        case RADIUS_PRIME_OVER_LOG: {
+            return d / log(p) - c * log1p(d / c) / (log(p) * log(c));
+        }
+        default: {
+            return d;
+        }
+    }
+}
+
+// Position of a prime in the polar layout by the radius law, at its angle rotated by
+// `rotation_rate`, relative to the view center. It is computed from the differences of the radius
+// and angle to the ones of the center, which are exact near the center, so the view can zoom into
+// any prime.
+fn polar_position(vertex: Vertex) -> vec2<f32> {
+    var d_radius = radius_law_difference(
+        globals.radius_law,
+        vertex.i_prime,
+        globals.center_prime,
+        globals.center_prime_fraction,
+    );
+    if globals.transition < 1.0 {
+        let previous = radius_law_difference(
+            globals.previous_radius_law,
+            vertex.i_prime,
+            globals.previous_center_prime,
+            globals.previous_center_prime_fraction,
+        );
+        d_radius = mix(previous, d_radius, globals.transition);
+    }
+    d_radius /= globals.radius_divisor;
+    let d_angle = f32(bitcast<i32>(vertex.i_turns - globals.center_turns)) * TURNS_TO_RADIANS;
+
+    // In the frame of the center, with x pointing away from the origin
+    let half_sin = sin(0.5 * d_angle);
+    let x = d_radius * cos(d_angle) - 2.0 * globals.center_radius * half_sin * half_sin;
+    let y = (globals.center_radius + d_radius) * sin(d_angle);
+    let c = cos(globals.center_angle);
+    let s = sin(globals.center_angle);
+    return vec2<f32>(x * c - y * s, x * s + y * c);
+}
+
+// Size law of the layer, mirroring `SizeLaw` in Rust.
+fn size_factor(vertex: Vertex) -> f32 {
+#ifdef SIZE_LOG
+    return log(f32(vertex.i_prime)) / 10.0;
+#else ifdef SIZE_GAP
+    return f32(vertex.i_gap) / log(f32(vertex.i_prime));
+#else
+    return 1.0;
+#endif
+}
+
+// Position of a prime in the 3D layouts, given its position in the polar layout.
+fn layout_position(layout_id: u32, position: vec2<f32>) -> vec3<f32> {
+    let radius = length(position);
+    let angle = atan2(position.y, position.x);
+    if layout_id == LAYOUT_CYLINDER {
+        return vec3<f32>(CYLINDER_RADIUS * cos(angle), radius, CYLINDER_RADIUS * sin(angle));
+    }
+    return vec3<f32>(radius * cos(angle), HELIX_PITCH * angle / (2.0 * PI), radius * sin(angle));
+}
+
+// Clip position of a vertex of the mesh of a prime, offset from the position of the prime relative
+// to the view center.
+fn layout_clip_position(layout_id: u32, position: vec2<f32>, offset: vec3<f32>) -> vec4<f32> {
+    if layout_id == LAYOUT_POLAR {
+        // The instances entity has an identity transform and the camera stays at the origin, so
+        // local space is relative to the view center. The slot of the entity in the mesh uniforms
+        // is not known here, as the instance index is used for the primes.
+        return mesh2d_position_world_to_clip(vec4<f32>(offset + vec3<f32>(position, 0.0), 1.0));
+    }
+
+    // The mesh is a billboard facing the orbit camera, which looks at the origin
+    let world_position = layout_position(layout_id, position + globals.center);
+    let view_position = globals.view_from_world * vec4<f32>(world_position, 1.0);
+    return globals.clip_from_view * (view_position + vec4<f32>(offset.xy, 0.0, 0.0));
+}
+
+// Places the mesh of a prime, given the outputs of the `position`, `scale` and `color` functions
+// of the style. The position is in world space.
+fn vertex_output(vertex: Vertex, position: vec2<f32>, scale: f32, color: vec4<f32>) -> VertexOutput {
+    return vertex_output_relative(vertex, position - globals.center, scale, color);
+}
+
+// Like `vertex_output`, with the position relative to the view center, which keeps its precision
+// far from the origin.
+fn vertex_output_relative(vertex: Vertex, position: vec2<f32>, scale: f32, color: vec4<f32>) -> VertexOutput {
+    let highlighted = (vertex.i_flags & FLAG_HIGHLIGHT) != 0u;
+    let offset = select(1.0, HIGHLIGHT_SCALE, highlighted) * globals.point_scale * scale * size_factor(vertex) * vertex.position;
+
+    var out: VertexOutput;
+    out.clip_position = layout_clip_position(globals.current_layout, position, offset);
+    if globals.transition < 1.0 {
+        // Every prime moves from its place in the previous layout to the one in the current layout
+        let previous = layout_clip_position(globals.previous_layout, position, offset);
+        out.clip_position = mix(previous, out.clip_position, globals.transition);
+    }
+    out.color = select(color, HIGHLIGHT_COLOR, highlighted);
+    out.uv = vertex.uv;
+    return out;
+}
+
+fn fragment_output(in: VertexOutput) -> vec4<f32> {
+#ifdef SHAPE_DISC
+    // Coverage of the pixel by the disc inscribed in the quad, antialiased over one pixel
+    let distance = length(2.0 * in.uv - 1.0);
+    let coverage = clamp((1.0 - distance) / fwidth(distance), 0.0, 1.0);
+#else
+    let coverage = 1.0;
+#endif
+
+#ifdef DENSITY
+    // Every prime adds one to the density of the pixels it covers
+    return vec4<f32>(coverage);
+#else
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
+#endif
+}
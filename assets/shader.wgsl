@@ -1,80 +1,40 @@
-#import bevy_sprite::mesh2d_functions::{get_world_from_local, mesh2d_position_local_to_clip}
-
-const PI: f32 = 3.141592653589793;
-
-struct Globals {
-    elapsed_seconds: f32,
-    zoom: f32,
-    _padding0: u32,
-    _padding1: u32,
-}
-
-@group(2) @binding(0)
-var<uniform> globals: Globals;
-
-struct Vertex {
-    @location(0) position: vec3<f32>,
-    @location(1) normal: vec3<f32>,
-    @location(2) uv: vec2<f32>,
-
-    @location(3) i_prime: u32,
-    @location(4) i_angle: f32,
-};
-
-struct VertexOutput {
-    @builtin(position) clip_position: vec4<f32>,
-    @location(0) color: vec4<f32>,
-};
-
-fn gamma_function(value: f32) -> f32 {
-    if value <= 0.0 {
-        return value;
-    }
-    if value <= 0.04045 {
-        return value / 12.92; // linear falloff in dark values
-    }
-    return pow((value + 0.055) / 1.055, 2.4); // gamma curve in other area
-}
-
-fn zoom_scale() -> f32 {
-    if globals.zoom > 0.0 {
-        return 1.0 / pow(1.5, globals.zoom);
-    }
-
-    return 1.0 / pow(1.75, globals.zoom);
+#import primes::instanced::{PI, globals, gamma_function, zoom_scale, radius, instance_angle, Vertex, VertexOutput, vertex_output, fragment_output}
+#import primes::instanced::{polar_position, vertex_output_relative}
+
+// The default style. User styles (see `styles/`) define the `position`, `scale` and `color`
+// functions, and get the first import above and the entry points below added when they are loaded.
+// The position of the default style is `polar_position`, which user styles can write as
+//
+//     radius(prime) * vec2<f32>(cos(angle - rotation), sin(angle - rotation))
+//
+// with `rotation = globals.rotation_rate * globals.elapsed_seconds`, but relative to the view
+// center, to keep deep zooms sharp.
+
+fn scale(prime: u32, angle: f32, index: u32) -> f32 {
+    let time = globals.elapsed_seconds;
+    let p = f32(prime);
+    return 0.1 * (0.4 + 0.8 * (sin(globals.pulse_frequency * time + p * globals.phase_factor) + 1.0) / 2.0) * zoom_scale() * (1.0 + 0.0000001 * p);
 }
 
-@vertex
-fn vertex(vertex: Vertex) -> VertexOutput {
+fn color(prime: u32, angle: f32, index: u32) -> vec4<f32> {
     let time = globals.elapsed_seconds;
-
-    let prime = f32(vertex.i_prime);
-    let angle = vertex.i_angle;
-
-    let scale = 0.1 * (0.4 + 0.8 * (sin(2.0 * time + prime * 0.1) + 1.0) / 2.0) * zoom_scale() * (1.0 + 0.0000001 * prime);
-    let color = vec4<f32>(
-        gamma_function(1.5 + 0.5 * (sin(1.0 * time + prime * 0.0008) + 1.0) / 2.0),
+    let p = f32(prime);
+    return vec4<f32>(
+        gamma_function(1.5 + 0.5 * (sin(1.0 * time + p * 0.0008) + 1.0) / 2.0),
         gamma_function(1.5),
-        gamma_function(1.5 + pow(1.0 / prime, 0.2)),
+        gamma_function(1.5 + pow(1.0 / p, 0.2)),
         1.0,
     );
+}
 
-    let position = scale * vertex.position + vec3<f32>(
-        prime * cos(angle - 0.002 * time) / 512.0,
-        prime * sin(angle - 0.002 * time) / 512.0,
-        0.0,
-    );
-
-    var out: VertexOutput;
-    out.clip_position = mesh2d_position_local_to_clip(
-        get_world_from_local(0u),
-        vec4<f32>(position, 1.0)
-    );
-    out.color = color;
-    return out;
+@vertex
+fn vertex(vertex: Vertex, @builtin(instance_index) index: u32) -> VertexOutput {
+    let prime = vertex.i_prime;
+    let angle = instance_angle(vertex);
+    return vertex_output_relative(vertex, polar_position(vertex), scale(prime, angle, index), color(prime, angle, index));
 }
 
 @fragment
 fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
-    return in.color;
+    return fragment_output(in);
 }
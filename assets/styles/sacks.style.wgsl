@@ -0,0 +1,20 @@
+// Sacks spiral: every natural number n sits at radius sqrt(n) and angle 2π sqrt(n), so the
+// squares line up on the positive x axis. Primes are colored by their residue mod 4.
+
+fn position(prime: u32, angle: f32, index: u32) -> vec2<f32> {
+    let r = sqrt(f32(prime));
+    let theta = 2.0 * PI * fract(r) - globals.rotation_rate * globals.elapsed_seconds;
+    return 32.0 * r * vec2<f32>(cos(theta), sin(theta)) / globals.radius_divisor;
+}
+
+fn scale(prime: u32, angle: f32, index: u32) -> f32 {
+    let pulse = sin(globals.pulse_frequency * globals.elapsed_seconds + f32(index) * globals.phase_factor);
+    return 0.05 * (1.0 + 0.25 * pulse) * zoom_scale();
+}
+
+fn color(prime: u32, angle: f32, index: u32) -> vec4<f32> {
+    if prime % 4u == 1u {
+        return vec4<f32>(gamma_function(1.0), gamma_function(0.45), gamma_function(0.2), 1.0);
+    }
+    return vec4<f32>(gamma_function(0.3), gamma_function(0.6), gamma_function(1.0), 1.0);
+}
@@ -1,14 +1,32 @@
+#[path = "src/number_theory.rs"]
+mod number_theory;
 #[path = "src/primes.rs"]
 mod primes;
 
-use primes::Primes;
-use std::fs;
+use primes::{Format, Primes};
+use std::{env, fs};
+
+/// Bound of the primes in `assets/primes.bin`, unless set by `PRIMES_MAX`. `PRIMES_MAX=0` keeps the
+/// existing file, e.g. one made by the `generate` subcommand.
+const DEFAULT_MAX: u32 = 5_000_000;
 
 fn main() {
     println!("cargo::rerun-if-changed=build.rs");
+    println!("cargo::rerun-if-changed=src/number_theory.rs");
+    println!("cargo::rerun-if-changed=src/primes.rs");
+    println!("cargo::rerun-if-env-changed=PRIMES_MAX");
+
+    let max = match env::var("PRIMES_MAX") {
+        Ok(max) => max.parse().expect("PRIMES_MAX must be a number"),
+        Err(_) => DEFAULT_MAX,
+    };
+    if max == 0 {
+        println!("Keeping the existing primes");
+        return;
+    }
 
-    let primes = Primes::build(5_000_000);
+    let primes = Primes::build(max);
     println!("Found {} primes", primes.primes().len());
 
-    fs::write("assets/primes.bin", bytemuck::cast_slice(primes.primes())).unwrap();
+    fs::write("assets/primes.bin", primes.encode(Format::Raw)).unwrap();
 }
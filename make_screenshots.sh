@@ -24,4 +24,7 @@ for resolution in "${resolutions[@]}"; do
   for zoom in "${zoom_levels[@]}"; do
     cargo run -r -- screenshot --width $width --height $height --time $time --zoom $zoom
   done
+
+  # All primes, framed for the aspect ratio
+  cargo run -r -- screenshot --width $width --height $height --time $time --fit
 done
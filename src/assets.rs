@@ -1,4 +1,9 @@
-use crate::{primes::Primes, AssetsState};
+use crate::{
+    primes::{Decoder, Format},
+    Args, AssetsState,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::asset::io::file::FileAssetReader;
 use bevy::{
     asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
     ecs::system::RunSystemOnce,
@@ -6,35 +11,128 @@ use bevy::{
     sprite::Mesh2dHandle,
 };
 use bevy_asset_loader::prelude::*;
+use std::{
+    mem,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+/// Key of the file of primes in `DynamicAssets`.
+const PRIMES_KEY: &str = "primes";
+
+/// Size of the chunks the file of primes is read in, decoded and handed to the game.
+const READ_CHUNK_SIZE: usize = 1 << 20;
 
 pub struct GameAssetsPlugin;
 
 impl Plugin for GameAssetsPlugin {
     fn build(&self, app: &mut App) {
+        let progress = LoadingProgress::default();
         app.init_asset::<PrimesAsset>()
-            .register_asset_loader(PrimesAssetLoader);
+            .insert_resource(progress.clone())
+            .register_asset_loader(PrimesAssetLoader { progress })
+            .add_systems(Startup, init_game_assets)
+            .add_systems(OnEnter(AssetsState::Loaded), log_loaded);
 
+        let primes = PrimesFile(app.world().resource::<Args>().primes().to_string());
+        app.world_mut()
+            .resource_mut::<DynamicAssets>()
+            .register_asset(PRIMES_KEY, Box::new(primes));
         app.configure_loading_state(
-            LoadingStateConfig::new(AssetsState::Loading)
-                .load_collection::<GameAssetsCollection>()
-                .init_resource::<GameAssets>(),
+            LoadingStateConfig::new(AssetsState::Loading).load_collection::<GameAssetsCollection>(),
         );
     }
 }
 
+/// Marks the end of loading the primes. The primes themselves are streamed to the game through
+/// `LoadingProgress` while they are read, so they are never held twice.
 #[derive(Debug, Clone, Asset, TypePath)]
-pub struct PrimesAsset(pub Primes);
+pub struct PrimesAsset {
+    /// Number of primes read.
+    pub count: usize,
+}
+
+/// Progress of loading the primes, updated by the asset loader while it runs in the background,
+/// with the primes read so far.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct LoadingProgress {
+    bytes_read: Arc<AtomicU64>,
+    primes_read: Arc<AtomicU64>,
+    chunks: Arc<Mutex<PrimeChunks>>,
+}
+
+/// Primes read by the loader and not taken by the game yet.
+#[derive(Debug, Default)]
+struct PrimeChunks {
+    /// Counts the loads, so the game drops the primes of a load that started over.
+    generation: u64,
+    chunks: Vec<Vec<u32>>,
+}
+
+impl LoadingProgress {
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read.load(Ordering::Relaxed)
+    }
+
+    pub fn primes_read(&self) -> u64 {
+        self.primes_read.load(Ordering::Relaxed)
+    }
+
+    /// The chunks of primes read since the last call, in ascending order, with the generation of
+    /// the load they belong to.
+    pub fn take_chunks(&self) -> (u64, Vec<Vec<u32>>) {
+        let mut chunks = self.chunks.lock().unwrap();
+        (chunks.generation, mem::take(&mut chunks.chunks))
+    }
+
+    fn start(&self) {
+        let mut chunks = self.chunks.lock().unwrap();
+        chunks.generation += 1;
+        chunks.chunks.clear();
+        self.bytes_read.store(0, Ordering::Relaxed);
+        self.primes_read.store(0, Ordering::Relaxed);
+    }
+
+    fn push(&self, primes: Vec<u32>, bytes: usize) {
+        self.primes_read
+            .fetch_add(primes.len() as u64, Ordering::Relaxed);
+        self.bytes_read.fetch_add(bytes as u64, Ordering::Relaxed);
+        if !primes.is_empty() {
+            self.chunks.lock().unwrap().chunks.push(primes);
+        }
+    }
+}
 
 #[derive(AssetCollection, Resource)]
 struct GameAssetsCollection {
-    #[asset(path = "primes.bin")]
+    #[asset(key = "primes")]
     primes: Handle<PrimesAsset>,
 }
 
+/// The file of primes given by `Args`, which the collection loads by `PRIMES_KEY`.
+#[derive(Debug)]
+struct PrimesFile(String);
+
+impl DynamicAsset for PrimesFile {
+    fn load(&self, asset_server: &AssetServer) -> Vec<UntypedHandle> {
+        vec![asset_server.load::<PrimesAsset>(&self.0).untyped()]
+    }
+
+    fn build(&self, world: &mut World) -> Result<DynamicAssetType, anyhow::Error> {
+        let handle = world.resource::<AssetServer>().load::<PrimesAsset>(&self.0);
+        Ok(DynamicAssetType::Single(handle.untyped()))
+    }
+}
+
 #[derive(Debug, Resource)]
 pub struct GameAssets {
     pub circle: Mesh2dHandle,
-    pub primes: Primes,
+    pub circle_low: Mesh2dHandle,
+    pub point: Mesh2dHandle,
+    pub square: Mesh2dHandle,
+    pub cross: Mesh2dHandle,
 }
 
 impl FromWorld for GameAssets {
@@ -43,18 +141,38 @@ impl FromWorld for GameAssets {
     }
 }
 
-fn load_assets(
-    mut meshes: ResMut<Assets<Mesh>>,
-    prime_assets: Res<Assets<PrimesAsset>>,
-    collection: Res<GameAssetsCollection>,
-) -> GameAssets {
+/// The meshes are built in code, so the game can start before the primes are loaded.
+fn init_game_assets(mut commands: Commands) {
+    commands.init_resource::<GameAssets>();
+}
+
+fn log_loaded(collection: Res<GameAssetsCollection>, prime_assets: Res<Assets<PrimesAsset>>) {
+    if let Some(primes) = prime_assets.get(&collection.primes) {
+        info!("Loaded {} primes", primes.count);
+    }
+}
+
+fn load_assets(mut meshes: ResMut<Assets<Mesh>>) -> GameAssets {
     GameAssets {
         circle: meshes.add(RegularPolygon::new(1.0, 16)).into(),
-        primes: prime_assets.get(&collection.primes).unwrap().0.clone(),
+        circle_low: meshes.add(RegularPolygon::new(1.0, 8)).into(),
+        point: meshes.add(RegularPolygon::new(1.0, 4)).into(),
+        square: meshes.add(Rectangle::new(2.0, 2.0)).into(),
+        cross: meshes.add(cross_mesh()).into(),
     }
 }
 
-pub struct PrimesAssetLoader;
+/// A plus sign of width 2, built from non-overlapping rectangles so no pixel is drawn twice.
+fn cross_mesh() -> Mesh {
+    let mut mesh = Mesh::from(Rectangle::new(2.0, 0.5));
+    mesh.merge(&Mesh::from(Rectangle::new(0.5, 0.75)).translated_by(Vec3::new(0.0, 0.625, 0.0)));
+    mesh.merge(&Mesh::from(Rectangle::new(0.5, 0.75)).translated_by(Vec3::new(0.0, -0.625, 0.0)));
+    mesh
+}
+
+pub struct PrimesAssetLoader {
+    progress: LoadingProgress,
+}
 
 impl AssetLoader for PrimesAssetLoader {
     type Asset = PrimesAsset;
@@ -65,16 +183,73 @@ impl AssetLoader for PrimesAssetLoader {
         &'a self,
         reader: &'a mut Reader<'_>,
         _settings: &'a (),
-        _load_context: &'a mut LoadContext<'_>,
+        load_context: &'a mut LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
-        let mut buf = Vec::new();
-        reader.read_to_end(&mut buf).await?;
-        let primes = Primes::from_unchecked(bytemuck::try_cast_slice(&buf)?.to_vec());
+        self.progress.start();
+        let mut decoder = Decoder::new(Format::from_path(load_context.path()));
+        let mut count = 0;
+
+        // Native files are mapped, so their bytes are paged in by the system instead of copied
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(map) = map_asset(load_context.path()) {
+            for chunk in map.chunks(READ_CHUNK_SIZE) {
+                count += self.decode(&mut decoder, chunk)?;
+            }
+            return self.finish(decoder, count);
+        }
 
-        Ok(PrimesAsset(primes))
+        // Only one chunk of bytes is held at a time, the primes go straight to the game
+        let mut chunk = vec![0; READ_CHUNK_SIZE];
+        loop {
+            let n = reader.read(&mut chunk).await?;
+            if n == 0 {
+                break;
+            }
+            count += self.decode(&mut decoder, &chunk[..n])?;
+        }
+        self.finish(decoder, count)
     }
 
     fn extensions(&self) -> &[&str] {
-        &["bin"]
+        &["bin", "gaps", "txt"]
+    }
+}
+
+impl PrimesAssetLoader {
+    /// Decodes a chunk of the file and hands its primes to the game, returning their number.
+    fn decode(
+        &self,
+        decoder: &mut Decoder,
+        bytes: &[u8],
+    ) -> Result<usize, <Self as AssetLoader>::Error> {
+        let mut primes = Vec::new();
+        decoder.decode(bytes, &mut primes)?;
+        let count = primes.len();
+        self.progress.push(primes, bytes.len());
+        Ok(count)
+    }
+
+    fn finish(
+        &self,
+        decoder: Decoder,
+        count: usize,
+    ) -> Result<PrimesAsset, <Self as AssetLoader>::Error> {
+        let mut primes = Vec::new();
+        decoder.finish(&mut primes)?;
+        let count = count + primes.len();
+        self.progress.push(primes, 0);
+        Ok(PrimesAsset { count })
     }
 }
+
+/// Maps the file of an asset of the default source into memory, or `None` if it can't be, in which
+/// case it is read through the asset reader.
+#[cfg(not(target_arch = "wasm32"))]
+fn map_asset(path: &std::path::Path) -> Option<memmap2::Mmap> {
+    let path = FileAssetReader::get_base_path()
+        .join(AssetPlugin::default().file_path)
+        .join(path);
+    let file = std::fs::File::open(path).ok()?;
+    // SAFETY: the file is only read, and is not expected to change while it is loaded
+    unsafe { memmap2::Mmap::map(&file) }.ok()
+}
@@ -4,10 +4,20 @@ use bevy::{
     render::camera::ScalingMode,
 };
 
+/// Height of the view of the game camera in world units, at zoom level 0.
+pub const VIEW_HEIGHT: f32 = 100.0;
+
+/// Marks the camera the player looks through, as opposed to offscreen cameras.
+#[derive(Debug, Default, Component)]
+pub struct GameCamera;
+
 #[derive(Bundle)]
 pub struct GameCameraBundle {
     pub camera: Camera2dBundle,
     pub bloom: BloomSettings,
+    pub game_camera: GameCamera,
+    /// UI is drawn by the game camera rather than the offscreen cameras.
+    pub default_ui_camera: IsDefaultUiCamera,
 }
 
 impl Default for GameCameraBundle {
@@ -21,13 +31,15 @@ impl Default for GameCameraBundle {
                 projection: OrthographicProjection {
                     far: 1000.,
                     near: -1000.,
-                    scaling_mode: ScalingMode::FixedVertical(100.0),
+                    scaling_mode: ScalingMode::FixedVertical(VIEW_HEIGHT),
                     ..Default::default()
                 },
                 tonemapping: Tonemapping::TonyMcMapface,
                 ..default()
             },
             bloom: BloomSettings::default(),
+            game_camera: GameCamera,
+            default_ui_camera: IsDefaultUiCamera,
         }
     }
 }
@@ -1,3 +1,4 @@
+use crate::{settings::Settings, Args};
 use bevy::prelude::*;
 use iyes_perf_ui::{entries::PerfUiBundle, prelude::*, PerfUiSet};
 
@@ -10,27 +11,39 @@ impl Plugin for DevPlugin {
             bevy::diagnostic::EntityCountDiagnosticsPlugin,
             iyes_perf_ui::PerfUiPlugin,
         ));
-        app.add_systems(Update, toggle.before(PerfUiSet::Setup));
+        app.add_systems(
+            Update,
+            (toggle, show)
+                .chain()
+                .before(PerfUiSet::Setup)
+                .run_if(|args: Res<Args>| matches!(*args, Args::Run { .. })),
+        );
     }
 }
 
-fn toggle(
-    mut commands: Commands,
-    query: Query<Entity, With<PerfUiRoot>>,
-    input: Res<ButtonInput<KeyCode>>,
-) {
+fn toggle(mut settings: ResMut<Settings>, input: Res<ButtonInput<KeyCode>>) {
     if input.just_pressed(KeyCode::F12) {
-        match query.get_single() {
-            Ok(e) => commands.entity(e).despawn_recursive(),
-            Err(_) => {
-                commands.spawn(PerfUiBundle {
-                    root: PerfUiRoot {
-                        position: PerfUiPosition::TopLeft,
-                        ..default()
-                    },
+        settings.hud.fps = !settings.hud.fps;
+    }
+}
+
+/// Shows the diagnostics while `Settings::hud` asks for them.
+fn show(mut commands: Commands, settings: Res<Settings>, query: Query<Entity, With<PerfUiRoot>>) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    match (query.get_single(), settings.hud.fps) {
+        (Ok(e), false) => commands.entity(e).despawn_recursive(),
+        (Err(_), true) => {
+            commands.spawn(PerfUiBundle {
+                root: PerfUiRoot {
+                    position: PerfUiPosition::TopLeft,
                     ..default()
-                });
-            }
+                },
+                ..default()
+            });
         }
+        _ => (),
     }
 }
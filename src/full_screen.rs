@@ -16,10 +16,14 @@ fn full_screen(
     keyboard_input: Res<ButtonInput<KeyCode>>,
 ) {
     if keyboard_input.just_pressed(KeyCode::F11) {
-        let mut window = primary_window.single_mut();
-        window.mode = match window.mode {
-            WindowMode::Windowed => WindowMode::BorderlessFullscreen,
-            _ => WindowMode::Windowed,
-        };
+        toggle(&mut primary_window.single_mut());
     }
 }
+
+/// Switches `window` between fullscreen and windowed.
+pub fn toggle(window: &mut Window) {
+    window.mode = match window.mode {
+        WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+        _ => WindowMode::Windowed,
+    };
+}
@@ -0,0 +1,524 @@
+use super::{
+    fit::Fit,
+    goto::{self, GoTo},
+    instanced::InstanceMaterialData,
+    orbit::Orbit,
+    share, GameTime, PrimesLayer, ViewCenter, Zoom,
+};
+use crate::{
+    settings::{checked_speed, Settings, MAX_SPEED},
+    AppState,
+};
+use bevy::{
+    ecs::system::SystemParam,
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    },
+    prelude::*,
+    window::PrimaryWindow,
+};
+use std::{f64::consts::TAU, time::Duration};
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Game), setup);
+        app.add_systems(OnExit(AppState::Game), cleanup);
+        app.add_systems(
+            Update,
+            (input.run_if(super::mode_is_run), update_text)
+                .chain()
+                .run_if(in_state(AppState::Game)),
+        );
+    }
+}
+
+/// Lines of output kept on screen.
+const OUTPUT_LINES: usize = 12;
+
+/// The console, toggled with <kbd>`</kbd>. Commands are typed into it and change the same
+/// resources as the controls.
+#[derive(Debug, Default, Resource)]
+pub struct Console {
+    /// The line being typed, while the console is open.
+    input: Option<String>,
+    /// Close the console after the next command, as it was opened for it with <kbd>G</kbd>.
+    close_after_run: bool,
+    output: Vec<String>,
+    history: Vec<String>,
+    /// The entry of the history shown in the input, while browsing it.
+    history_index: Option<usize>,
+}
+
+impl Console {
+    fn print(&mut self, text: &str) {
+        self.output.extend(text.lines().map(str::to_string));
+        let excess = self.output.len().saturating_sub(OUTPUT_LINES);
+        self.output.drain(..excess);
+    }
+}
+
+/// Whether the console takes the keyboard, so the other controls ignore it.
+pub fn is_open(console: Option<Res<Console>>) -> bool {
+    console.is_some_and(|console| console.input.is_some())
+}
+
+/// The resources the commands act on.
+#[derive(SystemParam)]
+struct Targets<'w, 's> {
+    commands: Commands<'w, 's>,
+    settings: ResMut<'w, Settings>,
+    game_time: ResMut<'w, GameTime>,
+    zoom: ResMut<'w, Zoom>,
+    view_center: Res<'w, ViewCenter>,
+    orbit: Res<'w, Orbit>,
+    goto: ResMut<'w, GoTo>,
+    layers: Query<'w, 's, &'static InstanceMaterialData, With<PrimesLayer>>,
+    window: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+}
+
+/// A command of the console.
+struct ConsoleCommand {
+    name: &'static str,
+    usage: &'static str,
+    help: &'static str,
+    /// Values of the argument, for completion.
+    values: &'static [&'static str],
+    run: fn(&mut Targets, &str) -> Result<String, String>,
+}
+
+const COMMANDS: &[ConsoleCommand] = &[
+    ConsoleCommand {
+        name: "help",
+        usage: "",
+        help: "list the commands",
+        values: &[],
+        run: |_, _| {
+            let lines = COMMANDS
+                .iter()
+                .map(|command| format!("{} {} - {}", command.name, command.usage, command.help));
+            Ok(lines.collect::<Vec<_>>().join("\n"))
+        },
+    },
+    ConsoleCommand {
+        name: "goto",
+        usage: "<number>",
+        help: "fly to a number or an expression like 2^31-1, nothing to clear",
+        values: &[],
+        run: |targets, arg| {
+            if arg.is_empty() {
+                targets.goto.clear(&mut targets.commands);
+                return Ok(String::new());
+            }
+            let number = goto::evaluate(arg)?;
+            Ok(targets.goto.go_to(
+                &mut targets.commands,
+                number,
+                targets.settings.layout,
+                &targets.view_center,
+                &targets.orbit,
+                &mut targets.zoom,
+            ))
+        },
+    },
+    ConsoleCommand {
+        name: "mod",
+        usage: "<modulus>",
+        help: "turn the residue classes of the modulus into rays",
+        values: &[],
+        run: |targets, arg| {
+            let modulus = arg.parse::<u32>().map_err(|e| e.to_string())?;
+            if modulus == 0 {
+                return Err("the modulus must be positive".into());
+            }
+            let animation = &mut targets.settings.animation;
+            animation.angle_multiplier = TAU / modulus as f64;
+            animation.angle_sweep = 0.0;
+            Ok(format!("angle multiplier 1/{modulus} turn"))
+        },
+    },
+    ConsoleCommand {
+        name: "speed",
+        usage: "<factor>",
+        help: "speed of the game time",
+        values: &[],
+        run: |targets, arg| {
+            let speed = arg.parse::<f64>().map_err(|e| e.to_string())?;
+            targets.settings.view.speed = checked_speed(speed)?;
+            if speed > MAX_SPEED as f64 {
+                return Ok(format!("speed limited to {MAX_SPEED}"));
+            }
+            Ok(String::new())
+        },
+    },
+    ConsoleCommand {
+        name: "time",
+        usage: "<milliseconds>",
+        help: "jump to a game time, like --time",
+        values: &[],
+        run: |targets, arg| {
+            let millis = arg.parse::<u64>().map_err(|e| e.to_string())?;
+            targets.game_time.elapsed = Duration::from_millis(millis);
+            Ok(String::new())
+        },
+    },
+    ConsoleCommand {
+        name: "zoom",
+        usage: "<level>",
+        help: "zoom to a level, 0 is the initial view",
+        values: &[],
+        run: |targets, arg| {
+            targets.zoom.target = arg.parse::<f32>().map_err(|e| e.to_string())?;
+            Ok(String::new())
+        },
+    },
+    ConsoleCommand {
+        name: "fit",
+        usage: "",
+        help: "frame all primes",
+        values: &[],
+        run: |targets, _| {
+            targets.commands.init_resource::<Fit>();
+            Ok(String::new())
+        },
+    },
+    ConsoleCommand {
+        name: "layout",
+        usage: "<layout>",
+        help: "switch the layout",
+        values: &["polar", "cylinder", "helix"],
+        run: |targets, arg| {
+            targets.settings.layout = arg.parse()?;
+            Ok(String::new())
+        },
+    },
+    ConsoleCommand {
+        name: "radius",
+        usage: "<law>",
+        help: "switch the radius law",
+        values: &["linear", "sqrt", "log", "prime_over_log"],
+        run: |targets, arg| {
+            targets.settings.radius_law = arg.parse()?;
+            Ok(String::new())
+        },
+    },
+    ConsoleCommand {
+        name: "screenshot",
+        usage: "[<width>x<height>]",
+        help: "render the view to ./screenshots, at the size of the window by default",
+        values: &[],
+        run: screenshot,
+    },
+    ConsoleCommand {
+        name: "export",
+        usage: "<path>",
+        help: "write the settings (.json) or the primes in view (.bin, .gaps, .txt)",
+        values: &[],
+        run: export,
+    },
+    ConsoleCommand {
+        name: "link",
+        usage: "",
+        help: "copy a link that opens the web demo at this view",
+        values: &[],
+        run: copy_link,
+    },
+];
+
+/// A link to the current view, copied to the clipboard in the web build and printed to be copied
+/// otherwise.
+fn copy_link(targets: &mut Targets, _: &str) -> Result<String, String> {
+    let link = share::view_link(
+        &targets.settings,
+        &targets.game_time,
+        &targets.zoom,
+        &targets.view_center,
+        &targets.goto,
+    );
+    let url = crate::link::url(&link.fragment());
+    #[cfg(target_arch = "wasm32")]
+    crate::link::copy(&url);
+    Ok(url)
+}
+
+/// Renders the view with the `screenshot` subcommand, in a process of its own, from the current
+/// settings, time, zoom and pan.
+#[cfg(not(target_arch = "wasm32"))]
+fn screenshot(targets: &mut Targets, arg: &str) -> Result<String, String> {
+    let (width, height) = match arg {
+        "" => {
+            let window = targets.window.get_single().map_err(|e| e.to_string())?;
+            (window.physical_width(), window.physical_height())
+        }
+        _ => {
+            let invalid = || format!("invalid size: {arg}");
+            let (width, height) = arg.split_once('x').ok_or_else(invalid)?;
+            (
+                width.parse::<u32>().map_err(|_| invalid())?,
+                height.parse::<u32>().map_err(|_| invalid())?,
+            )
+        }
+    };
+
+    let millis = targets.game_time.elapsed.as_millis();
+    let preset = serde_json::to_string(&*targets.settings).map_err(|e| e.to_string())?;
+    let center = targets.view_center.0;
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    // The settings are passed as a preset on the standard input, so no file is left behind
+    let mut child = std::process::Command::new(exe)
+        .arg("screenshot")
+        .args([
+            "--width",
+            &width.to_string(),
+            "--height",
+            &height.to_string(),
+        ])
+        .args(["--time", &millis.to_string()])
+        .args(["--zoom", &targets.zoom.current.to_string()])
+        .args(["--pan", &format!("{},{}", center.x, center.y)])
+        .args(["--preset", "-"])
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    // Dropping the pipe ends the input
+    std::io::Write::write_all(&mut child.stdin.take().unwrap(), preset.as_bytes())
+        .map_err(|e| e.to_string())?;
+    Ok(format!("rendering {width}x{height} to ./screenshots"))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn screenshot(_: &mut Targets, _: &str) -> Result<String, String> {
+    Err("screenshots are not available on the web".into())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn export(targets: &mut Targets, arg: &str) -> Result<String, String> {
+    if arg.is_empty() {
+        return Err("missing path".into());
+    }
+    let path = std::path::Path::new(arg);
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        targets
+            .settings
+            .save_preset(path)
+            .map_err(|e| e.to_string())?;
+        return Ok(format!("saved the settings to {arg}"));
+    }
+
+    let primes = targets
+        .layers
+        .iter()
+        .flat_map(InstanceMaterialData::visible_primes)
+        .collect::<Vec<_>>();
+    let count = primes.len();
+    let bytes = crate::Primes::from_unchecked(primes).encode(crate::Format::from_path(path));
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(path, bytes).map_err(|e| e.to_string())?;
+    Ok(format!("wrote {count} primes to {arg}"))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn export(_: &mut Targets, _: &str) -> Result<String, String> {
+    Err("export is not available on the web".into())
+}
+
+/// Runs a line typed into the console, returning its output.
+fn run(targets: &mut Targets, line: &str) -> Result<String, String> {
+    let (name, arg) = line.split_once(' ').unwrap_or((line, ""));
+    let Some(command) = COMMANDS.iter().find(|command| command.name == name) else {
+        return Err(format!("unknown command: {name}, see help"));
+    };
+    (command.run)(targets, arg.trim())
+}
+
+/// Completes the command or its argument, as far as the candidates agree. Lists the candidates if
+/// there are several.
+fn complete(line: &str) -> (String, Vec<&'static str>) {
+    let (prefix, partial, candidates) = match line.split_once(' ') {
+        None => (
+            "",
+            line,
+            COMMANDS.iter().map(|c| c.name).collect::<Vec<_>>(),
+        ),
+        Some((name, arg)) => match COMMANDS.iter().find(|command| command.name == name) {
+            Some(command) => (&line[..name.len() + 1], arg, command.values.to_vec()),
+            None => return (line.to_string(), Vec::new()),
+        },
+    };
+    let candidates = candidates
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(partial))
+        .collect::<Vec<_>>();
+
+    match candidates.as_slice() {
+        [] => (line.to_string(), Vec::new()),
+        [candidate] => {
+            let space = if prefix.is_empty() { " " } else { "" };
+            (format!("{prefix}{candidate}{space}"), Vec::new())
+        }
+        [first, rest @ ..] => {
+            let common = rest.iter().fold(first.len(), |common, candidate| {
+                first
+                    .bytes()
+                    .zip(candidate.bytes())
+                    .take(common)
+                    .take_while(|(a, b)| a == b)
+                    .count()
+            });
+            (format!("{prefix}{}", &first[..common]), candidates)
+        }
+    }
+}
+
+fn input(
+    mut console: ResMut<Console>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut keyboard_input: EventReader<KeyboardInput>,
+    mut targets: Targets,
+) {
+    let console = &mut *console;
+    let Some(mut line) = console.input.clone() else {
+        keyboard_input.clear();
+        if keys.just_pressed(KeyCode::Backquote) {
+            console.input = Some(String::new());
+        } else if keys.just_pressed(KeyCode::KeyG) {
+            console.input = Some("goto ".into());
+            console.close_after_run = true;
+        }
+        return;
+    };
+
+    for event in keyboard_input.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Escape => {
+                console.input = None;
+                console.close_after_run = false;
+                return;
+            }
+            Key::Character(c) if c.as_str() == "`" => {
+                console.input = None;
+                console.close_after_run = false;
+                return;
+            }
+            Key::Character(c) => line.push_str(c),
+            Key::Space => line.push(' '),
+            Key::Backspace => {
+                line.pop();
+            }
+            Key::Tab => {
+                let (completed, candidates) = complete(&line);
+                if !candidates.is_empty() {
+                    console.print(&candidates.join("  "));
+                }
+                line = completed;
+            }
+            Key::ArrowUp if !console.history.is_empty() => {
+                let index = match console.history_index {
+                    Some(index) => index.saturating_sub(1),
+                    None => console.history.len() - 1,
+                };
+                console.history_index = Some(index);
+                line.clone_from(&console.history[index]);
+            }
+            Key::ArrowDown => {
+                let index = console.history_index.map(|index| index + 1);
+                console.history_index = index.filter(|index| *index < console.history.len());
+                line = match console.history_index {
+                    Some(index) => console.history[index].clone(),
+                    None => String::new(),
+                };
+            }
+            Key::Enter => {
+                let command = line.trim().to_string();
+                line.clear();
+                console.history_index = None;
+                if command.is_empty() {
+                    continue;
+                }
+                if console.history.last() != Some(&command) {
+                    console.history.push(command.clone());
+                }
+
+                console.print(&format!("> {command}"));
+                match run(&mut targets, &command) {
+                    Ok(output) => console.print(&output),
+                    Err(e) => console.print(&format!("error: {e}")),
+                }
+                if console.close_after_run {
+                    console.input = None;
+                    console.close_after_run = false;
+                    return;
+                }
+            }
+            _ => (),
+        }
+    }
+    if console.input.as_ref() != Some(&line) {
+        console.input = Some(line);
+    }
+}
+
+/// Marks the text of the console.
+#[derive(Debug, Component)]
+struct ConsoleText;
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<Console>();
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                color: Color::srgb(0.9, 0.9, 0.9),
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Percent(25.0),
+            width: Val::Percent(50.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            ..default()
+        })
+        .with_background_color(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+        Visibility::Hidden,
+        ConsoleText,
+        StateScoped(AppState::Game),
+    ));
+}
+
+fn update_text(
+    console: Res<Console>,
+    mut text: Query<(&mut Text, &mut Visibility), With<ConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+    let Ok((mut text, mut visibility)) = text.get_single_mut() else {
+        return;
+    };
+
+    let Some(input) = &console.input else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    let mut lines = console.output.clone();
+    lines.push(format!("> {input}_"));
+    text.sections[0].value = lines.join("\n");
+    *visibility = Visibility::Inherited;
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<Console>();
+}
@@ -0,0 +1,160 @@
+use super::{
+    instanced::{InstanceMaterialData, HIGHLIGHT_SCALE},
+    style::UserStyle,
+    transition::Transition,
+    ViewCenter, Zoom,
+};
+use crate::{
+    assets::GameAssets,
+    camera::GameCamera,
+    settings::{AnimationSettings, PointShape, PointStyle, Settings, SizeLaw},
+};
+use bevy::{math::DVec2, prelude::*, sprite::Mesh2dHandle};
+
+/// Below this projected diameter (in pixels) a prime is drawn as a point sprite.
+const POINT_MAX_PIXELS: f32 = 3.0;
+
+/// Below this projected diameter (in pixels) a prime is drawn as a low detail polygon.
+const LOW_DETAIL_MAX_PIXELS: f32 = 12.0;
+
+/// Upper bound of the gap to the previous prime relative to `ln p`, for all primes that fit a `u32`.
+const MAX_GAP_MERIT: f64 = 40.0;
+
+/// Largest scale a prime can have at the given zoom, mirroring the pulse in `shader.wgsl`.
+fn max_point_scale(animation: &AnimationSettings, zoom: f32, prime: f64) -> f64 {
+    0.1 * 1.2
+        * animation.point_scale as f64
+        * animation.zoom_scale(zoom) as f64
+        * (1.0 + 0.0000001 * prime)
+}
+
+impl SizeLaw {
+    /// Largest factor of the size law for primes up to `prime`.
+    fn max_factor(self, prime: f64) -> f64 {
+        match self {
+            SizeLaw::Constant => 1.0,
+            SizeLaw::Log => f64::max(1.0, prime).ln() / 10.0,
+            SizeLaw::Gap => MAX_GAP_MERIT,
+        }
+    }
+
+    /// Factor of the size law for a typical prime around `prime`.
+    fn typical_factor(self, prime: f64) -> f64 {
+        match self {
+            SizeLaw::Constant | SizeLaw::Gap => 1.0,
+            SizeLaw::Log => f64::max(1.0, prime).ln() / 10.0,
+        }
+    }
+}
+
+/// Mesh of a point shape, with the detail of circles picked by their diameter in pixels.
+fn shape_mesh(assets: &GameAssets, shape: PointShape, diameter: Option<f32>) -> &Mesh2dHandle {
+    match (shape, diameter) {
+        (PointShape::Circle, Some(diameter)) if diameter < POINT_MAX_PIXELS => &assets.point,
+        (PointShape::Circle, Some(diameter)) if diameter < LOW_DETAIL_MAX_PIXELS => {
+            &assets.circle_low
+        }
+        (PointShape::Circle, _) => &assets.circle,
+        (PointShape::Square | PointShape::Disc, _) => &assets.square,
+        (PointShape::Cross, _) => &assets.cross,
+    }
+}
+
+/// The prime at `radius` by the radius laws of the transition, the smaller or larger one by
+/// `select`. The blended radius of a prime lies between its radii by both laws.
+pub fn prime_at(transition: &Transition, radius: f64, select: fn(f64, f64) -> f64) -> f64 {
+    let [from, to] = transition.radius_laws();
+    select(from.prime(radius), to.prime(radius))
+}
+
+pub fn cull_instances(
+    assets: Res<GameAssets>,
+    settings: Res<Settings>,
+    zoom: Res<Zoom>,
+    user_style: Res<UserStyle>,
+    transition: Res<Transition>,
+    view_center: Res<ViewCenter>,
+    camera: Query<(&Camera, &OrthographicProjection), With<GameCamera>>,
+    mut instances: Query<(
+        &mut InstanceMaterialData,
+        &mut Mesh2dHandle,
+        Option<&PointStyle>,
+    )>,
+) {
+    // The layout of user styles is unknown and the 3D layouts are seen through the orbit camera, so
+    // all of their primes are drawn in full detail
+    if user_style.shader().is_some() || transition.is_3d() {
+        for (mut instance, mut mesh, point_style) in &mut instances {
+            instance.set_visible_primes(0.0, settings.bound as f64);
+            let shape = point_style.copied().unwrap_or_default().shape;
+            let lod = shape_mesh(&assets, shape, None);
+            if mesh.0 != lod.0 {
+                *mesh = lod.clone();
+            }
+        }
+        return;
+    }
+
+    let Ok((camera, projection)) = camera.get_single() else {
+        return;
+    };
+
+    // Visible rect in world space, around the view center
+    let min = view_center.0 + projection.area.min.as_dvec2();
+    let max = view_center.0 + projection.area.max.as_dvec2();
+
+    // Range of distances from the origin covered by the visible rect
+    let closest = DVec2::clamp(DVec2::ZERO, min, max);
+    let farthest = DVec2::max(min.abs(), max.abs());
+    let min_distance = closest.length();
+    let max_distance = farthest.length();
+
+    // The constants of a transition in progress, as drawn
+    let animation = &transition.animation;
+    let radius_divisor = animation.radius_divisor as f64;
+    let world_per_pixel = camera
+        .physical_viewport_size()
+        .map(|size| projection.area.height() / size.y as f32);
+
+    for (mut instance, mut mesh, point_style) in &mut instances {
+        let point_style = point_style.copied().unwrap_or_default();
+
+        // Expand by the size of a prime, so partially visible primes are still drawn. The corners
+        // of squares stick out of the unit circle.
+        let max_prime = prime_at(&transition, max_distance * radius_divisor, f64::max);
+        let highlight_scale = match settings.highlights.is_empty() {
+            true => 1.0,
+            false => HIGHLIGHT_SCALE,
+        };
+        let margin = std::f64::consts::SQRT_2
+            * max_point_scale(animation, zoom.current, max_prime)
+            * point_style.size.max_factor(max_prime)
+            * highlight_scale;
+        instance.set_visible_primes(
+            prime_at(
+                &transition,
+                f64::max(0.0, (min_distance - margin) * radius_divisor),
+                f64::min,
+            ),
+            f64::min(
+                settings.bound as f64,
+                prime_at(
+                    &transition,
+                    (max_distance + margin) * radius_divisor,
+                    f64::max,
+                ),
+            ),
+        );
+
+        // Level of detail by the projected size of a typical prime
+        let diameter = world_per_pixel.map(|world_per_pixel| {
+            let scale = max_point_scale(animation, zoom.current, 0.0)
+                * point_style.size.typical_factor(max_prime);
+            2.0 * scale as f32 / world_per_pixel
+        });
+        let lod = shape_mesh(&assets, point_style.shape, diameter);
+        if mesh.0 != lod.0 {
+            *mesh = lod.clone();
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use super::instanced::InstanceMaterialData;
+use crate::{
+    camera::GameCamera,
+    settings::{ColorRamp, DensityScale, RenderMode, Settings},
+    AppState,
+};
+use bevy::{
+    core_pipeline::tonemapping::{DebandDither, Tonemapping},
+    prelude::*,
+    render::{
+        camera::{CameraUpdateSystem, RenderTarget},
+        extract_component::{ExtractComponent, ExtractComponentPlugin},
+        mesh::MeshVertexBufferLayoutRef,
+        render_asset::RenderAssetUsages,
+        render_resource::{
+            AsBindGroup, Extent3d, RenderPipelineDescriptor, ShaderRef,
+            SpecializedMeshPipelineError, TextureDimension, TextureFormat, TextureUsages,
+        },
+        view::RenderLayers,
+    },
+    sprite::{Material2d, Material2dKey, Material2dPlugin, MaterialMesh2dBundle},
+};
+
+/// Render layer of the instances while they are accumulated into the density texture.
+const DENSITY_LAYER: usize = 1;
+
+pub struct DensityPlugin;
+
+impl Plugin for DensityPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            Material2dPlugin::<DensityMaterial>::default(),
+            ExtractComponentPlugin::<DensityCamera>::default(),
+        ));
+
+        app.add_systems(OnExit(AppState::Game), cleanup);
+        app.add_systems(Update, update_render_mode.run_if(in_state(AppState::Game)));
+        app.add_systems(
+            PostUpdate,
+            (
+                sync_density_camera.before(CameraUpdateSystem),
+                fit_density_quad.after(CameraUpdateSystem),
+            )
+                .run_if(resource_exists::<Density>),
+        );
+    }
+}
+
+/// Offscreen camera that accumulates the primes into the density texture.
+#[derive(Debug, Clone, Copy, Component, ExtractComponent)]
+pub struct DensityCamera;
+
+#[derive(Debug, Component)]
+struct DensityQuad;
+
+#[derive(Debug, Resource)]
+struct Density {
+    image: Handle<Image>,
+    material: Handle<DensityMaterial>,
+    camera: Entity,
+    quad: Entity,
+}
+
+#[derive(Debug, Clone, Asset, TypePath, AsBindGroup)]
+#[bind_group_data(DensityMaterialKey)]
+struct DensityMaterial {
+    ramp: ColorRamp,
+    scale: DensityScale,
+    #[uniform(0)]
+    saturation: f32,
+    #[texture(1)]
+    #[sampler(2)]
+    density: Handle<Image>,
+}
+
+impl DensityMaterial {
+    fn update(&mut self, settings: &Settings) {
+        self.ramp = settings.density.ramp;
+        self.scale = settings.density.scale;
+        self.saturation = settings.density.saturation;
+    }
+}
+
+impl Material2d for DensityMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "density.wgsl".into()
+    }
+
+    fn specialize(
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayoutRef,
+        key: Material2dKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let shader_defs = &mut descriptor.fragment.as_mut().unwrap().shader_defs;
+        shader_defs.push(
+            match key.bind_group_data.ramp {
+                ColorRamp::Grayscale => "RAMP_GRAYSCALE",
+                ColorRamp::Viridis => "RAMP_VIRIDIS",
+                ColorRamp::Inferno => "RAMP_INFERNO",
+                ColorRamp::Magma => "RAMP_MAGMA",
+            }
+            .into(),
+        );
+        if key.bind_group_data.scale == DensityScale::Log {
+            shader_defs.push("SCALE_LOG".into());
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct DensityMaterialKey {
+    ramp: ColorRamp,
+    scale: DensityScale,
+}
+
+impl From<&DensityMaterial> for DensityMaterialKey {
+    fn from(material: &DensityMaterial) -> Self {
+        Self {
+            ramp: material.ramp,
+            scale: material.scale,
+        }
+    }
+}
+
+fn update_render_mode(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    density: Option<Res<Density>>,
+    instances: Query<Entity, With<InstanceMaterialData>>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<DensityMaterial>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    match (settings.render_mode, density) {
+        (RenderMode::Density, None) => {
+            let mut image = Image::new_fill(
+                Extent3d {
+                    width: 1,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+                TextureDimension::D2,
+                &[0; 8],
+                TextureFormat::Rgba16Float,
+                RenderAssetUsages::default(),
+            );
+            image.texture_descriptor.usage |= TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT
+                | TextureUsages::TEXTURE_BINDING;
+            let image = images.add(image);
+
+            let camera = commands
+                .spawn((
+                    Camera2dBundle {
+                        camera: Camera {
+                            hdr: true,
+                            order: -1,
+                            target: RenderTarget::Image(image.clone()),
+                            clear_color: ClearColorConfig::Custom(Color::BLACK),
+                            ..default()
+                        },
+                        tonemapping: Tonemapping::None,
+                        deband_dither: DebandDither::Disabled,
+                        ..default()
+                    },
+                    DensityCamera,
+                    RenderLayers::layer(DENSITY_LAYER),
+                    StateScoped(AppState::Game),
+                ))
+                .id();
+
+            let material = materials.add(DensityMaterial {
+                ramp: settings.density.ramp,
+                scale: settings.density.scale,
+                saturation: settings.density.saturation,
+                density: image.clone(),
+            });
+            let quad = commands
+                .spawn((
+                    MaterialMesh2dBundle {
+                        mesh: meshes.add(Rectangle::new(1.0, 1.0)).into(),
+                        material: material.clone(),
+                        ..default()
+                    },
+                    DensityQuad,
+                    StateScoped(AppState::Game),
+                ))
+                .id();
+
+            for entity in &instances {
+                commands
+                    .entity(entity)
+                    .insert(RenderLayers::layer(DENSITY_LAYER));
+            }
+
+            commands.insert_resource(Density {
+                image,
+                material,
+                camera,
+                quad,
+            });
+        }
+        (RenderMode::Density, Some(density)) => {
+            if let Some(material) = materials.get_mut(&density.material) {
+                material.update(&settings);
+            }
+        }
+        (RenderMode::Points, Some(density)) => {
+            commands.entity(density.camera).despawn_recursive();
+            commands.entity(density.quad).despawn_recursive();
+            for entity in &instances {
+                commands.entity(entity).remove::<RenderLayers>();
+            }
+            commands.remove_resource::<Density>();
+        }
+        (RenderMode::Points, None) => (),
+    }
+}
+
+/// Keeps the density camera looking at the same area as the game camera, at the same resolution.
+fn sync_density_camera(
+    density: Res<Density>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<DensityMaterial>>,
+    game_camera: Query<
+        (&Camera, &OrthographicProjection, &Transform),
+        (With<GameCamera>, Without<DensityCamera>),
+    >,
+    mut density_camera: Query<(&mut OrthographicProjection, &mut Transform), With<DensityCamera>>,
+) {
+    let Ok((camera, projection, transform)) = game_camera.get_single() else {
+        return;
+    };
+    let Ok((mut density_projection, mut density_transform)) = density_camera.get_single_mut()
+    else {
+        return;
+    };
+
+    *density_projection = projection.clone();
+    *density_transform = *transform;
+
+    let Some(size) = camera.physical_viewport_size() else {
+        return;
+    };
+    let Some(image) = images.get(&density.image) else {
+        return;
+    };
+    if image.size() != size {
+        images.get_mut(&density.image).unwrap().resize(Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        });
+
+        // Rebuild the bind group of the material, so it samples the resized texture
+        materials.get_mut(&density.material);
+    }
+}
+
+/// Stretches the quad showing the density texture over the whole view of the game camera.
+fn fit_density_quad(
+    game_camera: Query<(&OrthographicProjection, &Transform), With<GameCamera>>,
+    mut quad: Query<&mut Transform, (With<DensityQuad>, Without<GameCamera>)>,
+) {
+    let Ok((projection, transform)) = game_camera.get_single() else {
+        return;
+    };
+    let Ok(mut quad) = quad.get_single_mut() else {
+        return;
+    };
+
+    quad.translation = (transform.translation.truncate() + projection.area.center()).extend(0.0);
+    quad.scale = projection.area.size().extend(1.0);
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<Density>();
+}
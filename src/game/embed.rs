@@ -0,0 +1,334 @@
+//! JavaScript API of the web build, for pages that embed the plot and drive it from their content:
+//!
+//! ```js
+//! import init, { setTime, setLayout, on, capture } from './bevy_game.js'
+//!
+//! on('hover', (prime) => console.log(prime))
+//! setLayout('helix')
+//! const png = await capture()
+//! ```
+
+use super::{
+    culling, goto, instanced::InstanceMaterialData, style::UserStyle, transition::Transition,
+    GameTime, PrimesLayer, ViewCenter, Zoom,
+};
+use crate::{
+    camera::GameCamera,
+    settings::{checked_speed, Layout, Settings},
+};
+use bevy::{
+    math::DVec2,
+    prelude::*,
+    render::{texture::ImageFormat, view::screenshot::ScreenshotManager},
+    window::PrimaryWindow,
+};
+use js_sys::{Array, Function, Promise, Uint8Array};
+use std::{cell::RefCell, collections::VecDeque, io::Cursor, sync::Mutex, time::Duration};
+use wasm_bindgen::prelude::*;
+
+/// Pixels from a prime within which the cursor hovers it.
+const HOVER_PIXELS: f64 = 8.0;
+
+/// Most numbers searched for the hovered prime. Zoomed out further, no prime is hovered.
+const MAX_HOVER_NUMBERS: f64 = 20_000.0;
+
+/// Events that can be subscribed to with `on`.
+const EVENTS: &[&str] = &["hover"];
+
+/// A call of the API, applied to the app in the next frame.
+#[derive(Debug)]
+enum Request {
+    Time(Duration),
+    Zoom(f32),
+    Speed(f32),
+    Layout(Layout),
+    Highlights(Vec<u32>),
+    Capture,
+}
+
+static REQUESTS: Mutex<Vec<Request>> = Mutex::new(Vec::new());
+
+/// PNGs of the captures taken, in the order they were requested.
+static CAPTURED: Mutex<Vec<Result<Vec<u8>, String>>> = Mutex::new(Vec::new());
+
+thread_local! {
+    static LISTENERS: RefCell<Vec<(String, Function)>> = RefCell::default();
+    /// The `resolve` and `reject` functions of the promises of the captures in progress.
+    static CAPTURES: RefCell<VecDeque<(Function, Function)>> = RefCell::default();
+}
+
+fn request(request: Request) {
+    REQUESTS.lock().unwrap().push(request);
+}
+
+/// Jumps to a game time in milliseconds, up to `u64::MAX` like the `time` command.
+#[wasm_bindgen(js_name = setTime)]
+pub fn set_time(milliseconds: f64) -> Result<(), JsError> {
+    if !(0.0..=u64::MAX as f64).contains(&milliseconds) {
+        return Err(JsError::new(&format!("invalid time: {milliseconds}")));
+    }
+    request(Request::Time(Duration::from_secs_f64(
+        milliseconds / 1000.0,
+    )));
+    Ok(())
+}
+
+/// Zooms to a level, `0` is the initial view.
+#[wasm_bindgen(js_name = setZoom)]
+pub fn set_zoom(level: f32) {
+    request(Request::Zoom(level));
+}
+
+/// Sets the speed of the game time, limited to `MAX_SPEED`.
+#[wasm_bindgen(js_name = setSpeed)]
+pub fn set_speed(speed: f64) -> Result<(), JsError> {
+    request(Request::Speed(
+        checked_speed(speed).map_err(|e| JsError::new(&e))?,
+    ));
+    Ok(())
+}
+
+/// Switches to the layout `polar`, `cylinder` or `helix`.
+#[wasm_bindgen(js_name = setLayout)]
+pub fn set_layout(layout: &str) -> Result<(), JsError> {
+    request(Request::Layout(
+        layout.parse().map_err(|e: String| JsError::new(&e))?,
+    ));
+    Ok(())
+}
+
+/// Highlights the given primes, replacing the ones highlighted before.
+#[wasm_bindgen(js_name = setHighlights)]
+pub fn set_highlights(primes: Vec<u32>) {
+    request(Request::Highlights(primes));
+}
+
+/// Calls `callback` on `event`. `hover` passes the prime under the cursor, or `null` once the
+/// cursor leaves it.
+#[wasm_bindgen]
+pub fn on(event: &str, callback: Function) -> Result<(), JsError> {
+    if !EVENTS.contains(&event) {
+        return Err(JsError::new(&format!("unknown event: {event}")));
+    }
+    LISTENERS.with_borrow_mut(|listeners| listeners.push((event.to_string(), callback)));
+    Ok(())
+}
+
+/// Stops calling `callback` on `event`.
+#[wasm_bindgen]
+pub fn off(event: &str, callback: &Function) {
+    LISTENERS.with_borrow_mut(|listeners| {
+        listeners.retain(|(name, listener)| name != event || listener != callback);
+    });
+}
+
+/// Captures the next frame, resolving to a PNG `Blob`.
+#[wasm_bindgen]
+pub fn capture() -> Promise {
+    Promise::new(&mut |resolve, reject| {
+        CAPTURES.with_borrow_mut(|captures| captures.push_back((resolve, reject)));
+        request(Request::Capture);
+    })
+}
+
+fn emit(event: &str, value: &JsValue) {
+    // Cloned, as listeners may subscribe while they are called
+    let listeners = LISTENERS.with_borrow(|listeners| {
+        listeners
+            .iter()
+            .filter(|(name, _)| name == event)
+            .map(|(_, listener)| listener.clone())
+            .collect::<Vec<_>>()
+    });
+    for listener in listeners {
+        if let Err(e) = listener.call1(&JsValue::NULL, value) {
+            error!("Listener of {event} failed: {e:?}");
+        }
+    }
+}
+
+pub fn apply_requests(
+    mut settings: ResMut<Settings>,
+    mut game_time: ResMut<GameTime>,
+    mut zoom: ResMut<Zoom>,
+    mut screenshot_manager: ResMut<ScreenshotManager>,
+    window: Query<Entity, With<PrimaryWindow>>,
+) {
+    let requests = std::mem::take(&mut *REQUESTS.lock().unwrap());
+    let mut retry = Vec::new();
+    for request in requests {
+        match request {
+            Request::Time(elapsed) => game_time.elapsed = elapsed,
+            Request::Zoom(level) => zoom.target = level,
+            Request::Speed(speed) => settings.view.speed = speed,
+            Request::Layout(layout) => settings.layout = layout,
+            Request::Highlights(primes) => settings.highlights = primes,
+            Request::Capture => {
+                let Ok(window) = window.get_single() else {
+                    retry.push(request);
+                    continue;
+                };
+                // One capture per frame, the others are taken in the frames after
+                let taken = screenshot_manager.take_screenshot(window, |image| {
+                    CAPTURED.lock().unwrap().push(encode_png(image));
+                });
+                if taken.is_err() {
+                    retry.push(request);
+                }
+            }
+        }
+    }
+    REQUESTS.lock().unwrap().splice(0..0, retry);
+}
+
+fn encode_png(image: Image) -> Result<Vec<u8>, String> {
+    let image = image.try_into_dynamic().map_err(|e| e.to_string())?;
+    let format = ImageFormat::Png.as_image_crate_format().unwrap();
+    let mut png = Cursor::new(Vec::new());
+    // Without the alpha channel, which holds brightness with HDR
+    image
+        .to_rgb8()
+        .write_to(&mut png, format)
+        .map_err(|e| e.to_string())?;
+    Ok(png.into_inner())
+}
+
+/// Settles the promises of the captures taken.
+pub fn resolve_captures() {
+    let captured = std::mem::take(&mut *CAPTURED.lock().unwrap());
+    for png in captured {
+        let Some((resolve, reject)) = CAPTURES.with_borrow_mut(VecDeque::pop_front) else {
+            break;
+        };
+        let settled = match png
+            .map_err(|e| JsValue::from(JsError::new(&e)))
+            .and_then(blob)
+        {
+            Ok(blob) => resolve.call1(&JsValue::NULL, &blob),
+            Err(e) => reject.call1(&JsValue::NULL, &e),
+        };
+        if let Err(e) = settled {
+            error!("Failed to settle a capture: {e:?}");
+        }
+    }
+}
+
+fn blob(png: Vec<u8>) -> Result<JsValue, JsValue> {
+    let parts = Array::of1(&Uint8Array::from(png.as_slice()));
+    let options = web_sys::BlobPropertyBag::new();
+    js_sys::Reflect::set(&options, &"type".into(), &"image/png".into())?;
+    Ok(web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options)?.into())
+}
+
+/// Emits `hover` when the cursor comes to or leaves a prime.
+pub fn hover(
+    settings: Res<Settings>,
+    game_time: Res<GameTime>,
+    transition: Res<Transition>,
+    user_style: Res<UserStyle>,
+    view_center: Res<ViewCenter>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    camera: Query<(&Camera, &GlobalTransform, &OrthographicProjection), With<GameCamera>>,
+    layers: Query<&InstanceMaterialData, With<PrimesLayer>>,
+    mut hovered: Local<Option<u32>>,
+    mut last_view: Local<Option<HoverView>>,
+) {
+    if !LISTENERS.with_borrow(|listeners| listeners.iter().any(|(name, _)| name == "hover")) {
+        // Listeners subscribing later are told about the prime hovered then
+        *hovered = None;
+        *last_view = None;
+        return;
+    }
+
+    // The primes only move under the cursor if it moves, the view changes or time passes
+    let view = HoverView {
+        cursor: window.get_single().ok().and_then(Window::cursor_position),
+        camera: camera
+            .get_single()
+            .ok()
+            .map(|(_, transform, projection)| (transform.translation(), projection.area)),
+        view_center: view_center.0,
+        elapsed: game_time.elapsed,
+    };
+    if last_view.as_ref() == Some(&view) && !settings.is_changed() && !transition.is_changed() {
+        return;
+    }
+    *last_view = Some(view);
+
+    // The primes of user styles and the 3D layouts are not where the polar layout puts them
+    let prime = match user_style.shader().is_some() || transition.is_3d() {
+        true => None,
+        false => window
+            .get_single()
+            .ok()
+            .zip(camera.get_single().ok())
+            .and_then(|(window, camera)| {
+                hovered_prime(
+                    window,
+                    camera,
+                    &layers,
+                    settings.bound,
+                    &transition,
+                    &view_center,
+                    game_time.elapsed.as_secs_f64(),
+                )
+            }),
+    };
+
+    if prime != *hovered {
+        *hovered = prime;
+        emit("hover", &prime.map_or(JsValue::NULL, JsValue::from));
+    }
+}
+
+/// What the prime under the cursor depends on, besides the settings.
+#[derive(Debug, PartialEq)]
+pub struct HoverView {
+    cursor: Option<Vec2>,
+    camera: Option<(Vec3, Rect)>,
+    view_center: DVec2,
+    elapsed: Duration,
+}
+
+/// The prime closest to the cursor, within `HOVER_PIXELS`.
+fn hovered_prime(
+    window: &Window,
+    (camera, camera_transform, projection): (&Camera, &GlobalTransform, &OrthographicProjection),
+    layers: &Query<&InstanceMaterialData, With<PrimesLayer>>,
+    bound: u32,
+    transition: &Transition,
+    view_center: &ViewCenter,
+    elapsed: f64,
+) -> Option<u32> {
+    let cursor = window.cursor_position()?;
+    let cursor = view_center.0
+        + camera
+            .viewport_to_world_2d(camera_transform, cursor)?
+            .as_dvec2();
+    let reach = HOVER_PIXELS * projection.area.height() as f64 / window.height() as f64;
+
+    // The numbers within reach of the radius of the cursor, by both radius laws of a transition
+    let radius_divisor = transition.animation.radius_divisor as f64;
+    let distance = cursor.length();
+    let min = culling::prime_at(
+        transition,
+        f64::max(0.0, distance - reach) * radius_divisor,
+        f64::min,
+    );
+    let max = culling::prime_at(transition, (distance + reach) * radius_divisor, f64::max);
+    let max = f64::min(max, bound as f64);
+    if max - min > MAX_HOVER_NUMBERS {
+        return None;
+    }
+
+    layers
+        .iter()
+        .flat_map(|layer| layer.primes_in(min, max))
+        .map(|prime| {
+            let position = goto::polar_position(prime, transition, elapsed);
+            (prime, position.distance(cursor))
+        })
+        .filter(|(_, distance)| *distance <= reach)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(prime, _)| prime)
+}
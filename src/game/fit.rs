@@ -0,0 +1,95 @@
+use super::{
+    goto, instanced::InstanceMaterialData, orbit, transition::Transition, PrimesLayer, ViewCenter,
+    Zoom,
+};
+use crate::{
+    camera::{GameCamera, VIEW_HEIGHT},
+    settings::{Layout, Settings},
+    Args,
+};
+use bevy::{math::DVec2, prelude::*};
+
+/// Radius of the cylinder layout, mirroring `CYLINDER_RADIUS` in `instanced.wgsl`.
+const CYLINDER_RADIUS: f64 = 20.0;
+
+/// Height of one turn of the helix layout, mirroring `HELIX_PITCH` in `instanced.wgsl`.
+const HELIX_PITCH: f64 = 50.0;
+
+/// Space around the framed content, relative to its size.
+const MARGIN: f64 = 1.05;
+
+/// A request to frame the plot, handled once the primes and the viewport are known. The view is
+/// centered on the origin, as all layouts are.
+#[derive(Debug, Default, Resource)]
+pub struct Fit {
+    /// Radius of the disc to frame in world units, instead of the disc of all loaded primes.
+    pub radius: Option<f64>,
+}
+
+/// Zoom level that frames the bounding disc of radius `radius` of the polar layout, or the content
+/// of the 3D layouts built from it, in a viewport of the given aspect ratio.
+fn zoom_to_fit(layout: Layout, radius: f64, aspect_ratio: f64) -> f32 {
+    let scale = match layout {
+        Layout::Polar => 2.0 * radius / (VIEW_HEIGHT as f64 * f64::min(1.0, aspect_ratio)),
+        Layout::Cylinder | Layout::Helix => {
+            // Bounding sphere around the target of the orbit camera, which looks at the origin
+            let sphere = match layout {
+                Layout::Cylinder => f64::hypot(radius, CYLINDER_RADIUS),
+                _ => f64::hypot(radius, 0.5 * HELIX_PITCH),
+            };
+            let half_fov = 0.5 * orbit::FOV as f64;
+            let half_fov = f64::min(half_fov, f64::atan(half_fov.tan() * aspect_ratio));
+            sphere / half_fov.sin() / orbit::DISTANCE as f64
+        }
+    };
+    -(MARGIN * scale).log2() as f32
+}
+
+pub fn fit(
+    mut commands: Commands,
+    fit: Res<Fit>,
+    args: Res<Args>,
+    settings: Res<Settings>,
+    transition: Res<Transition>,
+    mut zoom: ResMut<Zoom>,
+    mut view_center: ResMut<ViewCenter>,
+    layers: Query<&InstanceMaterialData, With<PrimesLayer>>,
+    mut camera: Query<(&Camera, &mut OrthographicProjection), With<GameCamera>>,
+) {
+    let Ok((camera, mut projection)) = camera.get_single_mut() else {
+        return;
+    };
+    let Some(viewport_size) = camera.logical_viewport_size() else {
+        return;
+    };
+
+    // The largest radius of the primes by both radius laws of a transition
+    let radius = match fit.radius {
+        Some(radius) => radius,
+        None => {
+            let Some(max_prime) = layers.iter().filter_map(|layer| layer.max_prime()).max() else {
+                return;
+            };
+            let max_prime = u32::min(max_prime, settings.bound);
+            let [from, to] = transition.radius_laws();
+            let radius = f64::max(from.radius(max_prime as f64), to.radius(max_prime as f64));
+            radius / transition.animation.radius_divisor as f64
+        }
+    };
+
+    zoom.target = zoom_to_fit(
+        settings.layout,
+        radius,
+        viewport_size.x as f64 / viewport_size.y as f64,
+    );
+    // Screenshots are taken right away, the app zooms smoothly
+    if let Args::Screenshot { .. } = *args {
+        zoom.current = zoom.target;
+        projection.scale = zoom.scale();
+    }
+    // A flight to a number would pull the view away from the framed plot
+    commands.remove_resource::<goto::Flight>();
+    view_center.0 = DVec2::ZERO;
+
+    commands.remove_resource::<Fit>();
+}
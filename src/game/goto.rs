@@ -0,0 +1,392 @@
+use super::{instanced, orbit::Orbit, transition::Transition, GameTime, ViewCenter, Zoom};
+use crate::{
+    number_theory,
+    settings::{Easing, Layout, Settings},
+    AppState, Args,
+};
+use bevy::{math::DVec2, prelude::*};
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+pub struct GoToPlugin;
+
+impl Plugin for GoToPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::Game),
+            (setup, start).chain().after(super::setup),
+        );
+        app.add_systems(OnExit(AppState::Game), cleanup);
+        app.add_systems(
+            Update,
+            (fly.run_if(resource_exists::<Flight>), update_text)
+                .chain()
+                .after(super::transition::transition)
+                .run_if(in_state(AppState::Game)),
+        );
+    }
+}
+
+/// Seconds a flight to a number takes.
+const FLIGHT_DURATION: f32 = 2.0;
+
+/// Zoom level a flight zooms in to at least, close enough to tell the primes apart.
+const FLIGHT_ZOOM: f32 = 6.0;
+
+/// The number gone to with the `goto` command of the console.
+#[derive(Debug, Default, Resource)]
+pub struct GoTo {
+    /// The number gone to, highlighted if it is a prime.
+    target: Option<u64>,
+    /// What is known about the target.
+    message: String,
+}
+
+impl GoTo {
+    /// The number gone to.
+    pub fn target(&self) -> Option<u64> {
+        self.target
+    }
+
+    /// The target, if it is a prime in the plot.
+    pub fn highlight(&self) -> Option<u32> {
+        self.target
+            .filter(|target| number_theory::is_prime(*target))
+            .and_then(|target| u32::try_from(target).ok())
+    }
+}
+
+/// The camera flying to a number. It pans along while the plot rotates, until the view is panned.
+#[derive(Debug, Resource)]
+pub struct Flight {
+    number: u32,
+    from: DVec2,
+    from_yaw: f32,
+    /// Seconds since the start of the flight.
+    elapsed: f32,
+}
+
+/// Evaluates an integer expression of `+`, `-`, `*`, `/`, `^` and parentheses, such as `2^31-1`.
+pub fn evaluate(expression: &str) -> Result<u64, String> {
+    let mut parser = Parser {
+        chars: expression.chars().filter(|c| !c.is_whitespace()).collect(),
+        position: 0,
+    };
+    let value = parser.sum()?;
+    match parser.peek() {
+        Some(c) => Err(format!("unexpected character: {c}")),
+        None => Ok(value),
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn sum(&mut self) -> Result<u64, String> {
+        let mut value = self.product()?;
+        loop {
+            value = match () {
+                _ if self.eat('+') => value.checked_add(self.product()?),
+                _ if self.eat('-') => value.checked_sub(self.product()?),
+                _ => return Ok(value),
+            }
+            .ok_or("out of range")?;
+        }
+    }
+
+    fn product(&mut self) -> Result<u64, String> {
+        let mut value = self.power()?;
+        loop {
+            value = match () {
+                _ if self.eat('*') => value.checked_mul(self.power()?).ok_or("out of range")?,
+                _ if self.eat('/') => value.checked_div(self.power()?).ok_or("division by zero")?,
+                _ => return Ok(value),
+            };
+        }
+    }
+
+    fn power(&mut self) -> Result<u64, String> {
+        let base = self.atom()?;
+        if !self.eat('^') {
+            return Ok(base);
+        }
+        // Right associative, `2^3^2` is `2^9`
+        let exponent = u32::try_from(self.power()?).map_err(|_| "out of range")?;
+        base.checked_pow(exponent)
+            .ok_or_else(|| "out of range".into())
+    }
+
+    fn atom(&mut self) -> Result<u64, String> {
+        if self.eat('(') {
+            let value = self.sum()?;
+            if !self.eat(')') {
+                return Err("missing )".into());
+            }
+            return Ok(value);
+        }
+
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.position += 1;
+        }
+        if start == self.position {
+            return Err(match self.peek() {
+                Some(c) => format!("unexpected character: {c}"),
+                None => "missing number".into(),
+            });
+        }
+        let digits = self.chars[start..self.position].iter().collect::<String>();
+        digits.parse().map_err(|_| "out of range".into())
+    }
+}
+
+/// What is known about `number`, its factors if it is composite.
+fn describe(number: u64) -> String {
+    if number < 2 {
+        return format!("{number} is neither prime nor composite");
+    }
+    let factors = number_theory::factorize(number);
+    let description = match factors.len() {
+        1 => format!("{number} is prime"),
+        _ => {
+            let mut powers: Vec<(u64, u32)> = Vec::new();
+            for factor in factors {
+                match powers.last_mut() {
+                    Some((last, exponent)) if *last == factor => *exponent += 1,
+                    _ => powers.push((factor, 1)),
+                }
+            }
+            let powers = powers
+                .iter()
+                .map(|(factor, exponent)| match exponent {
+                    1 => factor.to_string(),
+                    _ => format!("{factor}^{exponent}"),
+                })
+                .collect::<Vec<_>>();
+            format!("{number} = {}", powers.join(" * "))
+        }
+    };
+    match number > u32::MAX as u64 {
+        true => format!("{description}, beyond the plot"),
+        false => description,
+    }
+}
+
+/// Position of `number` in the polar layout, mirroring `radius` and `polar_position` in
+/// `instanced.wgsl`.
+pub fn polar_position(number: u32, transition: &Transition, elapsed: f64) -> DVec2 {
+    let animation = &transition.animation;
+    let [from, to] = transition.radius_laws();
+    let radius = f64::lerp(
+        from.radius(number as f64),
+        to.radius(number as f64),
+        transition.progress as f64,
+    ) / animation.radius_divisor as f64;
+    let turns = animation.angle_multiplier_at(elapsed) / TAU;
+    let angle = instanced::angle(number, turns) as f64 / instanced::TURN * TAU
+        - animation.rotation_rate as f64 * elapsed;
+    radius * DVec2::from_angle(angle)
+}
+
+impl GoTo {
+    /// Goes to `number`, flying there if it lies in the plot. Returns what is known about it.
+    pub fn go_to(
+        &mut self,
+        commands: &mut Commands,
+        number: u64,
+        layout: Layout,
+        view_center: &ViewCenter,
+        orbit: &Orbit,
+        zoom: &mut Zoom,
+    ) -> String {
+        self.target = Some(number);
+        self.message = describe(number);
+        if let Some(number) = u32::try_from(number).ok().filter(|n| *n >= 2) {
+            commands.insert_resource(Flight {
+                number,
+                from: view_center.0,
+                from_yaw: orbit.yaw,
+                elapsed: 0.0,
+            });
+            if !layout.is_3d() {
+                zoom.target = f32::max(zoom.target, FLIGHT_ZOOM);
+            }
+        }
+        self.message.clone()
+    }
+
+    /// Forgets the target and ends the flight to it.
+    pub fn clear(&mut self, commands: &mut Commands) {
+        *self = GoTo::default();
+        commands.remove_resource::<Flight>();
+    }
+}
+
+fn fly(
+    mut commands: Commands,
+    time: Res<Time>,
+    game_time: Res<GameTime>,
+    settings: Res<Settings>,
+    transition: Res<Transition>,
+    mut flight: ResMut<Flight>,
+    mut view_center: ResMut<ViewCenter>,
+    mut orbit: ResMut<Orbit>,
+) {
+    flight.elapsed += time.delta_seconds();
+    let t = f32::min(1.0, flight.elapsed / FLIGHT_DURATION);
+    let eased = Easing::CubicInOut.ease(t);
+
+    let target = polar_position(flight.number, &transition, game_time.elapsed.as_secs_f64());
+    if settings.layout.is_3d() {
+        // Turn the orbit camera to face the number, the shorter way round
+        let yaw = (FRAC_PI_2 - target.to_angle()) as f32;
+        let delta = (yaw - flight.from_yaw + PI as f32).rem_euclid(TAU as f32) - PI as f32;
+        orbit.yaw = flight.from_yaw + eased * delta;
+        if t >= 1.0 {
+            commands.remove_resource::<Flight>();
+        }
+    } else {
+        view_center.0 = flight.from.lerp(target, eased as f64);
+    }
+}
+
+/// Marks the text of the result.
+#[derive(Debug, Component)]
+struct GoToText;
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<GoTo>();
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                color: Color::srgb(0.9, 0.9, 0.9),
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            right: Val::Px(10.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            ..default()
+        })
+        .with_background_color(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+        Visibility::Hidden,
+        GoToText,
+        StateScoped(AppState::Game),
+    ));
+}
+
+/// Goes to the number the app was started with.
+fn start(
+    mut commands: Commands,
+    args: Res<Args>,
+    settings: Res<Settings>,
+    mut goto: ResMut<GoTo>,
+    view_center: Res<ViewCenter>,
+    orbit: Res<Orbit>,
+    mut zoom: ResMut<Zoom>,
+) {
+    if let Args::Run {
+        goto: Some(number), ..
+    } = *args
+    {
+        goto.go_to(
+            &mut commands,
+            number,
+            settings.layout,
+            &view_center,
+            &orbit,
+            &mut zoom,
+        );
+    }
+}
+
+fn update_text(
+    goto: Res<GoTo>,
+    settings: Res<Settings>,
+    mut text: Query<(&mut Text, &mut Visibility), With<GoToText>>,
+) {
+    if !goto.is_changed() && !settings.is_changed() {
+        return;
+    }
+    let Ok((mut text, mut visibility)) = text.get_single_mut() else {
+        return;
+    };
+
+    text.sections[0].value.clone_from(&goto.message);
+    *visibility = match goto.message.is_empty() || !settings.hud.info {
+        true => Visibility::Hidden,
+        false => Visibility::Inherited,
+    };
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<GoTo>();
+    commands.remove_resource::<Flight>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluate_follows_precedence() {
+        assert_eq!(evaluate("2^3^2"), Ok(512));
+        assert_eq!(evaluate("(2^3)^2"), Ok(64));
+        assert_eq!(evaluate("2^31 - 1"), Ok(2_147_483_647));
+        assert_eq!(evaluate("1 + 2 * 3"), Ok(7));
+        assert_eq!(evaluate("(1 + 2) * 3"), Ok(9));
+        assert_eq!(evaluate("7 - 2 - 1"), Ok(4));
+        assert_eq!(evaluate("7 / 2"), Ok(3));
+        assert_eq!(evaluate("18446744073709551615"), Ok(u64::MAX));
+    }
+
+    #[test]
+    fn evaluate_reports_overflow() {
+        for expression in [
+            "2^64",
+            "18446744073709551616",
+            "2^32 * 2^32",
+            "1 - 2",
+            "2^2^2^2^2",
+        ] {
+            assert_eq!(
+                evaluate(expression),
+                Err("out of range".into()),
+                "{expression}"
+            );
+        }
+    }
+
+    #[test]
+    fn evaluate_reports_division_by_zero() {
+        assert_eq!(evaluate("1/0"), Err("division by zero".into()));
+        assert_eq!(evaluate("1/(2-2)"), Err("division by zero".into()));
+    }
+
+    #[test]
+    fn evaluate_reports_unbalanced_parentheses() {
+        assert_eq!(evaluate("(1+2"), Err("missing )".into()));
+        assert_eq!(evaluate("((1)"), Err("missing )".into()));
+        assert_eq!(evaluate("1+2)"), Err("unexpected character: )".into()));
+        assert_eq!(evaluate(")"), Err("unexpected character: )".into()));
+        assert_eq!(evaluate(""), Err("missing number".into()));
+    }
+}
@@ -1,11 +1,18 @@
-use super::{GameTime, Zoom};
+use super::{
+    density::DensityCamera, orbit::Orbit, style::UserStyle, transition::Transition, GameTime,
+    ViewCenter, Zoom,
+};
+use crate::{
+    camera::GameCamera,
+    settings::{Layout, PointShape, PointStyle, RadiusLaw, Settings, SizeLaw},
+};
 use bevy::{
     core_pipeline::core_2d::Transparent2d,
     ecs::{
         query::QueryItem,
         system::{lifetimeless::*, SystemParamItem},
     },
-    math::FloatOrd,
+    math::{DVec2, FloatOrd},
     prelude::*,
     render::{
         extract_component::{ExtractComponent, ExtractComponentPlugin},
@@ -17,20 +24,21 @@ use bevy::{
         },
         render_resource::*,
         renderer::{RenderDevice, RenderQueue},
-        view::ExtractedView,
+        view::{ExtractedView, RenderLayers},
         Extract, Render, RenderApp, RenderSet,
     },
     sprite::{
         Mesh2dPipeline, Mesh2dPipelineKey, RenderMesh2dInstances, SetMesh2dBindGroup,
         SetMesh2dViewBindGroup,
     },
+    tasks::{ComputeTaskPool, ParallelSliceMut},
 };
 use bytemuck::{Pod, Zeroable};
 use std::{
-    f32::consts::TAU,
+    ops::Range,
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc, OnceLock,
+        Arc, Mutex,
     },
 };
 
@@ -38,7 +46,10 @@ pub struct InstancedPlugin;
 
 impl Plugin for InstancedPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(ExtractComponentPlugin::<InstanceMaterialData>::default());
+        app.add_plugins((
+            ExtractComponentPlugin::<InstanceMaterialData>::default(),
+            ExtractComponentPlugin::<PointStyle>::default(),
+        ));
         app.sub_app_mut(RenderApp)
             .add_render_command::<Transparent2d, DrawCustom>()
             .init_resource::<SpecializedMeshPipelines<CustomPipeline>>()
@@ -60,48 +71,231 @@ impl Plugin for InstancedPlugin {
     }
 }
 
-#[derive(Component)]
+/// Instances shared with the render world. Changes are tracked, so only the changed instances are
+/// uploaded.
+#[derive(Component, Default)]
 pub struct InstanceMaterialData {
-    data: Arc<Vec<InstanceData>>,
-    buffer: Arc<OnceLock<InstanceBuffer>>,
+    instances: Arc<Mutex<Instances>>,
     rendered: Arc<AtomicBool>,
+    visible: Range<u32>,
+    render_layers: RenderLayers,
 }
 
-impl InstanceMaterialData {
-    pub fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> Self {
+struct Instances {
+    data: Vec<InstanceData>,
+    /// Radians per unit of the prime, see `AnimationSettings::angle_multiplier`.
+    angle_multiplier: f64,
+    /// Highlighted primes, sorted. Also applied to primes appended later.
+    highlights: Vec<u32>,
+    /// Holds at least `data.len()` instances, up to date except for the `dirty` ranges.
+    buffer: Option<Buffer>,
+    dirty: Vec<Range<usize>>,
+}
+
+impl Default for Instances {
+    fn default() -> Self {
         Self {
-            data: Arc::new(
-                iter.into_iter()
-                    .map(|prime| InstanceData(prime, prime as f32 % TAU))
-                    .collect(),
-            ),
-            buffer: Arc::new(OnceLock::new()),
-            rendered: Arc::new(AtomicBool::new(false)),
+            data: Vec::new(),
+            angle_multiplier: 1.0,
+            highlights: Vec::new(),
+            buffer: None,
+            dirty: Vec::new(),
+        }
+    }
+}
+
+/// Units of `InstanceData::turns` per turn. Fixed point keeps the full precision of the angle for
+/// the differences to the angle of the view center.
+pub const TURN: f64 = (1u64 << 32) as f64;
+
+/// Angle of `prime` for a multiplier of `turns` turns per unit, as a fraction of a turn in units of
+/// `TURN`.
+///
+/// `prime * turns` is far beyond the precision of its fraction for large primes, so `turns` is
+/// split into a head of 21 significant bits, whose product with any `u32` is exact in an `f64`,
+/// and the small rest. The whole turns are dropped from the exact product before the rest is added.
+pub fn angle(prime: u32, turns: f64) -> u32 {
+    let head = f64::from_bits(turns.to_bits() & !((1 << 32) - 1));
+    let tail = turns - head;
+    let exact = prime as f64 * head;
+    let fraction = (exact - exact.floor()) + prime as f64 * tail;
+    (fraction.rem_euclid(1.0) * TURN) as u32
+}
+
+/// Instances per task when the angles are recomputed.
+const ANGLE_BATCH_SIZE: usize = 1 << 16;
+
+/// Scale of highlighted primes, mirroring `HIGHLIGHT_SCALE` in `instanced.wgsl`.
+pub const HIGHLIGHT_SCALE: f64 = 4.0;
+
+/// Bits of `InstanceData::flags`, mirroring `FLAG_*` in `instanced.wgsl`.
+const FLAG_HIGHLIGHT: u32 = 1;
+
+impl Instances {
+    fn index_of(&self, prime: u32) -> Option<usize> {
+        self.data.binary_search_by_key(&prime, |i| i.prime).ok()
+    }
+
+    fn set_flag(&mut self, index: usize, flag: u32, value: bool) {
+        let flags = &mut self.data[index].flags;
+        let new = if value { *flags | flag } else { *flags & !flag };
+        if *flags != new {
+            *flags = new;
+            self.dirty.push(index..index + 1);
+        }
+    }
+}
+
+impl InstanceMaterialData {
+    /// Appends primes larger than all primes so far. Only the new instances are uploaded.
+    pub fn extend<I: IntoIterator<Item = u32>>(&mut self, iter: I) {
+        let mut instances = self.instances.lock().unwrap();
+        let instances = &mut *instances;
+        let start = instances.data.len();
+        let mut previous = instances.data.last().map(|instance| instance.prime);
+        let highlights = &instances.highlights;
+        let turns = instances.angle_multiplier / std::f64::consts::TAU;
+        instances.data.extend(iter.into_iter().map(|prime| {
+            // Saturating, as a corrupt primes file may not be increasing
+            let gap = previous.map_or(1, |previous| prime.saturating_sub(previous));
+            previous = Some(prime);
+            let flags = match highlights.binary_search(&prime) {
+                Ok(_) => FLAG_HIGHLIGHT,
+                Err(_) => 0,
+            };
+            InstanceData {
+                prime,
+                turns: angle(prime, turns),
+                gap,
+                flags,
+            }
+        }));
+        let end = instances.data.len();
+        instances.dirty.push(start..end);
+    }
+
+    /// Drops the primes above `max`, e.g. to narrow the range without loading it again.
+    pub fn truncate(&mut self, max: u32) {
+        let mut instances = self.instances.lock().unwrap();
+        let len = instances.data.partition_point(|i| i.prime <= max);
+        instances.data.truncate(len);
+    }
+
+    /// Removes all instances, e.g. when the primes are loaded again. Highlights are kept.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+        self.rendered.store(false, Ordering::Relaxed);
+    }
+
+    /// Highlights exactly the given primes, including ones appended later. Only the instances whose
+    /// highlight changed are uploaded.
+    pub fn set_highlights(&mut self, primes: &[u32]) {
+        let mut instances = self.instances.lock().unwrap();
+        let mut highlights = primes.to_vec();
+        highlights.sort_unstable();
+        highlights.dedup();
+
+        for prime in std::mem::take(&mut instances.highlights) {
+            if let Some(index) = instances.index_of(prime) {
+                instances.set_flag(index, FLAG_HIGHLIGHT, false);
+            }
+        }
+        for prime in &highlights {
+            if let Some(index) = instances.index_of(*prime) {
+                instances.set_flag(index, FLAG_HIGHLIGHT, true);
+            }
         }
+        instances.highlights = highlights;
+    }
+
+    /// Recomputes the angles of all primes for a new multiplier, on all cores.
+    pub fn set_angle_multiplier(&mut self, angle_multiplier: f64) {
+        let mut instances = self.instances.lock().unwrap();
+        if instances.angle_multiplier == angle_multiplier {
+            return;
+        }
+        instances.angle_multiplier = angle_multiplier;
+
+        let turns = angle_multiplier / std::f64::consts::TAU;
+        instances
+            .data
+            .par_chunk_map_mut(ComputeTaskPool::get(), ANGLE_BATCH_SIZE, |_, batch| {
+                for instance in batch {
+                    instance.turns = angle(instance.prime, turns);
+                }
+            });
+        let len = instances.data.len();
+        instances.dirty.push(0..len);
+    }
+
+    /// The primes drawn, see `set_visible_primes`.
+    pub fn visible_primes(&self) -> Vec<u32> {
+        let instances = self.instances.lock().unwrap();
+        let end = usize::min(self.visible.end as usize, instances.data.len());
+        let start = usize::min(self.visible.start as usize, end);
+        instances.data[start..end]
+            .iter()
+            .map(|instance| instance.prime)
+            .collect()
+    }
+
+    /// The primes in `min..=max`. Instances are sorted by prime, so they are found by binary
+    /// search.
+    #[cfg(target_arch = "wasm32")]
+    pub fn primes_in(&self, min: f64, max: f64) -> Vec<u32> {
+        let instances = self.instances.lock().unwrap();
+        let start = instances.data.partition_point(|i| (i.prime as f64) < min);
+        let end = instances.data.partition_point(|i| (i.prime as f64) <= max);
+        instances.data[start..usize::max(start, end)]
+            .iter()
+            .map(|instance| instance.prime)
+            .collect()
+    }
+
+    pub fn max_prime(&self) -> Option<u32> {
+        let instances = self.instances.lock().unwrap();
+        instances.data.last().map(|instance| instance.prime)
     }
 
     pub fn has_rendered(&self) -> bool {
         self.rendered.load(Ordering::Relaxed)
     }
+
+    /// Restricts drawing to the instances whose prime lies in `min..=max`. Instances are sorted by
+    /// prime, so this is a contiguous range.
+    pub fn set_visible_primes(&mut self, min: f64, max: f64) {
+        let instances = self.instances.lock().unwrap();
+        let start = instances.data.partition_point(|i| (i.prime as f64) < min);
+        let end = instances.data.partition_point(|i| (i.prime as f64) <= max);
+        self.visible = start as u32..u32::max(start as u32, end as u32);
+    }
 }
 
 impl ExtractComponent for InstanceMaterialData {
-    type QueryData = &'static InstanceMaterialData;
+    type QueryData = (&'static InstanceMaterialData, Option<&'static RenderLayers>);
     type QueryFilter = ();
     type Out = Self;
 
-    fn extract_component(item: QueryItem<'_, Self::QueryData>) -> Option<Self> {
+    fn extract_component((item, render_layers): QueryItem<'_, Self::QueryData>) -> Option<Self> {
         Some(InstanceMaterialData {
-            data: Arc::clone(&item.data),
-            buffer: Arc::clone(&item.buffer),
+            instances: Arc::clone(&item.instances),
             rendered: Arc::clone(&item.rendered),
+            visible: item.visible.clone(),
+            render_layers: render_layers.cloned().unwrap_or_default(),
         })
     }
 }
 
 #[derive(Clone, Copy, Pod, Zeroable)]
 #[repr(C)]
-pub struct InstanceData(u32, f32);
+pub struct InstanceData {
+    prime: u32,
+    /// Angle in units of `TURN`.
+    turns: u32,
+    /// Gap to the previous prime.
+    gap: u32,
+    flags: u32,
+}
 
 fn queue_custom(
     transparent_2d_draw_functions: Res<DrawFunctions<Transparent2d>>,
@@ -109,34 +303,68 @@ fn queue_custom(
     msaa: Res<Msaa>,
     mut pipelines: ResMut<SpecializedMeshPipelines<CustomPipeline>>,
     pipeline_cache: Res<PipelineCache>,
+    user_style: Res<UserStyle>,
     meshes: Res<RenderAssets<GpuMesh>>,
     render_mesh_instances: ResMut<RenderMesh2dInstances>,
-    material_meshes: Query<Entity, With<InstanceMaterialData>>,
+    material_meshes: Query<(Entity, &InstanceMaterialData, Option<&PointStyle>)>,
     mut transparent_render_phases: ResMut<ViewSortedRenderPhases<Transparent2d>>,
-    mut views: Query<(Entity, &ExtractedView)>,
+    mut views: Query<(
+        Entity,
+        &ExtractedView,
+        Option<&RenderLayers>,
+        Has<DensityCamera>,
+    )>,
 ) {
     let draw_custom = transparent_2d_draw_functions.read().id::<DrawCustom>();
 
     let msaa_key = Mesh2dPipelineKey::from_msaa_samples(msaa.samples());
 
-    for (view_entity, view) in &mut views {
+    for (view_entity, view, view_layers, density) in &mut views {
         let Some(transparent_phase) = transparent_render_phases.get_mut(&view_entity) else {
             continue;
         };
+        let view_layers = view_layers.cloned().unwrap_or_default();
 
         let view_key = msaa_key | Mesh2dPipelineKey::from_hdr(view.hdr);
-        for entity in &material_meshes {
+        for (entity, instance_material_data, point_style) in &material_meshes {
+            if !view_layers.intersects(&instance_material_data.render_layers) {
+                continue;
+            }
             let Some(mesh_instance) = render_mesh_instances.get(&entity) else {
                 continue;
             };
             let Some(mesh) = meshes.get(mesh_instance.mesh_asset_id) else {
                 continue;
             };
-            let key =
-                view_key | Mesh2dPipelineKey::from_primitive_topology(mesh.primitive_topology());
-            let pipeline = pipelines
+            let key = CustomPipelineKey {
+                mesh_key: view_key
+                    | Mesh2dPipelineKey::from_primitive_topology(mesh.primitive_topology()),
+                density,
+                point_style: point_style.copied().unwrap_or_default(),
+                style: None,
+            };
+            let mut pipeline = pipelines
                 .specialize(&pipeline_cache, &custom_pipeline, key, &mesh.layout)
                 .unwrap();
+
+            // Draw with the default style until the user style has compiled, or if it failed to
+            if let Some(style) = user_style.shader() {
+                let key = CustomPipelineKey {
+                    style: Some(style),
+                    ..key
+                };
+                let user_pipeline = pipelines
+                    .specialize(&pipeline_cache, &custom_pipeline, key, &mesh.layout)
+                    .unwrap();
+                // Pipelines queued in this frame are not in the cache yet
+                let state = pipeline_cache
+                    .pipelines()
+                    .nth(user_pipeline.id())
+                    .map(|pipeline| &pipeline.state);
+                if state.is_some_and(|state| user_style.update_status(state)) {
+                    pipeline = user_pipeline;
+                }
+            }
             transparent_phase.add(Transparent2d {
                 sort_key: FloatOrd(mesh_instance.transforms.world_from_local.translation.z),
                 entity,
@@ -149,23 +377,78 @@ fn queue_custom(
     }
 }
 
+/// The instance buffer of an entity in this frame.
+#[derive(Component)]
 struct InstanceBuffer {
     buffer: Buffer,
     length: usize,
 }
 
+/// Smallest instance buffer, in instances, so the first chunks of primes fit without growing it.
+const MIN_INSTANCE_CAPACITY: usize = 1 << 16;
+
 fn prepare_instance_buffers(
+    mut commands: Commands,
     query: Query<(Entity, &InstanceMaterialData)>,
     render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
 ) {
-    for (_entity, instance_data) in &query {
-        instance_data.buffer.get_or_init(|| InstanceBuffer {
-            buffer: render_device.create_buffer_with_data(&BufferInitDescriptor {
+    const STRIDE: usize = std::mem::size_of::<InstanceData>();
+
+    for (entity, instance_data) in &query {
+        let mut instances = instance_data.instances.lock().unwrap();
+        let instances = &mut *instances;
+        if instances.data.is_empty() {
+            continue;
+        }
+
+        // Grow by doubling, so streamed primes are copied a constant number of times on average
+        let capacity = instances
+            .buffer
+            .as_ref()
+            .map_or(0, |buffer| buffer.size() as usize / STRIDE);
+        if capacity < instances.data.len() {
+            let capacity = usize::max(
+                MIN_INSTANCE_CAPACITY,
+                instances.data.len().next_power_of_two(),
+            );
+            instances.buffer = Some(render_device.create_buffer(&BufferDescriptor {
                 label: Some("instance data buffer"),
-                contents: bytemuck::cast_slice(instance_data.data.as_slice()),
+                size: (capacity * STRIDE) as u64,
                 usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
-            }),
-            length: instance_data.data.len(),
+                mapped_at_creation: false,
+            }));
+            let len = instances.data.len();
+            instances.dirty.clear();
+            instances.dirty.push(0..len);
+        }
+        let buffer = instances.buffer.as_ref().unwrap();
+
+        // Upload the dirty ranges, merged where they touch and clipped to truncated data
+        let mut dirty = std::mem::take(&mut instances.dirty);
+        dirty.sort_unstable_by_key(|range| range.start);
+        let mut merged: Vec<Range<usize>> = Vec::new();
+        for range in dirty {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        for range in merged {
+            let range = range.start..usize::min(range.end, instances.data.len());
+            if range.is_empty() {
+                continue;
+            }
+            render_queue.write_buffer(
+                buffer,
+                (range.start * STRIDE) as u64,
+                bytemuck::cast_slice(&instances.data[range]),
+            );
+        }
+
+        commands.entity(entity).insert(InstanceBuffer {
+            buffer: buffer.clone(),
+            length: instances.data.len(),
         });
     }
 }
@@ -175,24 +458,144 @@ fn prepare_instance_buffers(
 struct Globals {
     elapsed_seconds: f32,
     zoom: f32,
-    _padding: [u32; 2],
+    pulse_frequency: f32,
+    phase_factor: f32,
+    rotation_rate: f32,
+    radius_divisor: f32,
+    zoom_base_in: f32,
+    zoom_base_out: f32,
+    /// View and projection of the orbit camera, used by the 3D layouts.
+    view_from_world: Mat4,
+    clip_from_view: Mat4,
+    current_layout: u32,
+    /// The layout blended from, by the eased `transition` progress.
+    previous_layout: u32,
+    transition: f32,
+    radius_law: u32,
+    previous_radius_law: u32,
+    /// The view center in polar coordinates, see `polar_position` in `instanced.wgsl`. Its angle
+    /// includes the rotation, in units of `TURN`.
+    center_turns: u32,
+    /// The prime at the radius of the center by the radius law, split into its integer part and
+    /// fraction, for both laws of the transition.
+    center_prime: u32,
+    previous_center_prime: u32,
+    center_prime_fraction: f32,
+    previous_center_prime_fraction: f32,
+    center_radius: f32,
+    center_angle: f32,
+    center: Vec2,
+    point_scale: f32,
+    _padding: u32,
+}
+
+fn layout_index(layout: Layout) -> u32 {
+    match layout {
+        Layout::Polar => 0,
+        Layout::Cylinder => 1,
+        Layout::Helix => 2,
+    }
+}
+
+fn radius_law_index(radius_law: RadiusLaw) -> u32 {
+    match radius_law {
+        RadiusLaw::Linear => 0,
+        RadiusLaw::Sqrt => 1,
+        RadiusLaw::Log => 2,
+        RadiusLaw::PrimeOverLog => 3,
+    }
+}
+
+/// The prime at `radius` by `radius_law`, split into its integer part and fraction.
+fn split_prime_at(radius_law: RadiusLaw, radius: f64) -> (u32, f32) {
+    let prime = radius_law.prime(radius).clamp(0.0, u32::MAX as f64);
+    (prime as u32, prime.fract() as f32)
 }
 
 fn extract_globals(
     mut commands: Commands,
     game_time: Extract<Option<Res<GameTime>>>,
     zoom: Extract<Option<Res<Zoom>>>,
+    settings: Extract<Res<Settings>>,
+    orbit: Extract<Option<Res<Orbit>>>,
+    transition: Extract<Option<Res<Transition>>>,
+    view_center: Extract<Option<Res<ViewCenter>>>,
+    camera: Extract<Query<&Camera, With<GameCamera>>>,
 ) {
+    let animation = match transition.as_ref() {
+        Some(transition) => &transition.animation,
+        None => &settings.animation,
+    };
+    let zoom_scale = match zoom.as_ref() {
+        Some(zoom) => zoom.scale(),
+        None => 1.0,
+    };
+    let aspect_ratio = match camera
+        .get_single()
+        .ok()
+        .and_then(Camera::logical_viewport_size)
+    {
+        Some(size) => size.x / size.y,
+        None => 1.0,
+    };
+    let elapsed_seconds = match game_time.as_ref() {
+        Some(game_time) => game_time.elapsed.as_secs_f64(),
+        None => 0.0,
+    };
+    let [previous_radius_law, radius_law] = match transition.as_ref() {
+        Some(transition) => transition.radius_laws(),
+        None => [settings.radius_law; 2],
+    };
+
+    // The primes are placed relative to the view center, see `polar_position` in `instanced.wgsl`
+    let center = view_center.as_ref().map_or(DVec2::ZERO, |center| center.0);
+    let center_radius = center.length();
+    let center_angle = center.y.atan2(center.x);
+    let center_turns =
+        (center_angle + animation.rotation_rate as f64 * elapsed_seconds) / std::f64::consts::TAU;
+    let radius = center_radius * animation.radius_divisor as f64;
+    let (center_prime, center_prime_fraction) = split_prime_at(radius_law, radius);
+    let (previous_center_prime, previous_center_prime_fraction) =
+        split_prime_at(previous_radius_law, radius);
+
     commands.insert_resource(Globals {
-        elapsed_seconds: match game_time.as_ref() {
-            Some(game_time) => game_time.elapsed.as_secs_f32(),
-            None => 0.0,
-        },
+        elapsed_seconds: elapsed_seconds as f32,
         zoom: match zoom.as_ref() {
             Some(zoom) => zoom.current,
             None => 1.0,
         },
-        _padding: [0; 2],
+        pulse_frequency: animation.pulse_frequency,
+        phase_factor: animation.phase_factor,
+        rotation_rate: animation.rotation_rate,
+        radius_divisor: animation.radius_divisor,
+        zoom_base_in: animation.zoom_base_in,
+        zoom_base_out: animation.zoom_base_out,
+        view_from_world: orbit
+            .as_ref()
+            .map(|orbit| orbit.view_from_world(zoom_scale))
+            .unwrap_or_default(),
+        clip_from_view: Orbit::clip_from_view(aspect_ratio),
+        current_layout: layout_index(settings.layout),
+        previous_layout: layout_index(match transition.as_ref() {
+            Some(transition) => transition.from_layout,
+            None => settings.layout,
+        }),
+        transition: match transition.as_ref() {
+            Some(transition) => transition.progress,
+            None => 1.0,
+        },
+        radius_law: radius_law_index(radius_law),
+        previous_radius_law: radius_law_index(previous_radius_law),
+        center_turns: (center_turns.rem_euclid(1.0) * TURN) as u32,
+        center_prime,
+        previous_center_prime,
+        center_prime_fraction,
+        previous_center_prime_fraction,
+        center_radius: center_radius as f32,
+        center_angle: center_angle as f32,
+        center: center.as_vec2(),
+        point_scale: animation.point_scale,
+        _padding: 0,
     });
 }
 
@@ -241,6 +644,8 @@ fn prepare_gpu_data(
 #[derive(Resource)]
 struct CustomPipeline {
     shader: Handle<Shader>,
+    /// The `primes::instanced` module imported by `shader.wgsl` and user styles.
+    _instanced_shader: Handle<Shader>,
     mesh_pipeline: Mesh2dPipeline,
     globals_layout: BindGroupLayout,
 }
@@ -252,6 +657,7 @@ impl FromWorld for CustomPipeline {
 
         CustomPipeline {
             shader: world.load_asset("shader.wgsl"),
+            _instanced_shader: world.load_asset("instanced.wgsl"),
             mesh_pipeline: mesh_pipeline.clone(),
             globals_layout: render_device.create_bind_group_layout(
                 "globals layout",
@@ -270,18 +676,58 @@ impl FromWorld for CustomPipeline {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct CustomPipelineKey {
+    mesh_key: Mesh2dPipelineKey,
+    /// Accumulate primes additively into a density texture instead of drawing colored points.
+    density: bool,
+    point_style: PointStyle,
+    /// A user style replacing `shader.wgsl`.
+    style: Option<AssetId<Shader>>,
+}
+
 impl SpecializedMeshPipeline for CustomPipeline {
-    type Key = Mesh2dPipelineKey;
+    type Key = CustomPipelineKey;
 
     fn specialize(
         &self,
         key: Self::Key,
         layout: &MeshVertexBufferLayoutRef,
     ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
-        let mut descriptor = self.mesh_pipeline.specialize(key, layout)?;
+        let mut descriptor = self.mesh_pipeline.specialize(key.mesh_key, layout)?;
+
+        let shader = match key.style {
+            Some(style) => Handle::Weak(style),
+            None => self.shader.clone(),
+        };
+        descriptor.vertex.shader = shader.clone();
+        descriptor.fragment.as_mut().unwrap().shader = shader;
+
+        match key.point_style.size {
+            SizeLaw::Constant => (),
+            SizeLaw::Log => descriptor.vertex.shader_defs.push("SIZE_LOG".into()),
+            SizeLaw::Gap => descriptor.vertex.shader_defs.push("SIZE_GAP".into()),
+        }
+        if key.point_style.shape == PointShape::Disc {
+            let fragment = descriptor.fragment.as_mut().unwrap();
+            fragment.shader_defs.push("SHAPE_DISC".into());
+        }
 
-        descriptor.vertex.shader = self.shader.clone();
-        descriptor.fragment.as_mut().unwrap().shader = self.shader.clone();
+        if key.density {
+            let additive = BlendComponent {
+                src_factor: BlendFactor::One,
+                dst_factor: BlendFactor::One,
+                operation: BlendOperation::Add,
+            };
+            let fragment = descriptor.fragment.as_mut().unwrap();
+            fragment.shader_defs.push("DENSITY".into());
+            for target in fragment.targets.iter_mut().flatten() {
+                target.blend = Some(BlendState {
+                    color: additive,
+                    alpha: additive,
+                });
+            }
+        }
 
         descriptor.layout.insert(2, self.globals_layout.clone());
 
@@ -295,10 +741,20 @@ impl SpecializedMeshPipeline for CustomPipeline {
                     shader_location: 3, // shader locations 0-2 are taken up by Position, Normal and UV attributes
                 },
                 VertexAttribute {
-                    format: VertexFormat::Float32,
+                    format: VertexFormat::Uint32,
                     offset: 4,
                     shader_location: 4,
                 },
+                VertexAttribute {
+                    format: VertexFormat::Uint32,
+                    offset: 8,
+                    shader_location: 5,
+                },
+                VertexAttribute {
+                    format: VertexFormat::Uint32,
+                    offset: 12,
+                    shader_location: 6,
+                },
             ],
         });
 
@@ -322,13 +778,13 @@ impl<P: PhaseItem> RenderCommand<P> for DrawMeshInstanced {
         SRes<RenderMesh2dInstances>,
     );
     type ViewQuery = ();
-    type ItemQuery = Read<InstanceMaterialData>;
+    type ItemQuery = (Read<InstanceMaterialData>, Read<InstanceBuffer>);
 
     #[inline]
     fn render<'w>(
         item: &P,
         _view: (),
-        instance_material_data: Option<&'w InstanceMaterialData>,
+        instances: Option<(&'w InstanceMaterialData, &'w InstanceBuffer)>,
         (globals, meshes, render_mesh_instances): SystemParamItem<'w, '_, Self::Param>,
         pass: &mut TrackedRenderPass<'w>,
     ) -> RenderCommandResult {
@@ -339,10 +795,13 @@ impl<P: PhaseItem> RenderCommand<P> for DrawMeshInstanced {
         let Some(gpu_mesh) = meshes.into_inner().get(mesh_instance.mesh_asset_id) else {
             return RenderCommandResult::Failure;
         };
-        let Some(instance_material_data) = instance_material_data else {
-            return RenderCommandResult::Failure;
+        // Nothing to draw until the first primes arrive
+        let Some((instance_material_data, instance_buffer)) = instances else {
+            return RenderCommandResult::Success;
         };
-        let instance_buffer = instance_material_data.buffer.get().unwrap();
+        let length = instance_buffer.length as u32;
+        let visible = &instance_material_data.visible;
+        let instances = u32::min(visible.start, length)..u32::min(visible.end, length);
 
         pass.set_vertex_buffer(0, gpu_mesh.vertex_buffer.slice(..));
         pass.set_vertex_buffer(1, instance_buffer.buffer.slice(..));
@@ -356,10 +815,10 @@ impl<P: PhaseItem> RenderCommand<P> for DrawMeshInstanced {
                 count,
             } => {
                 pass.set_index_buffer(buffer.slice(..), 0, *index_format);
-                pass.draw_indexed(0..*count, 0, 0..instance_buffer.length as u32);
+                pass.draw_indexed(0..*count, 0, instances);
             }
             GpuBufferInfo::NonIndexed => {
-                pass.draw(0..gpu_mesh.vertex_count, 0..instance_buffer.length as u32);
+                pass.draw(0..gpu_mesh.vertex_count, instances);
             }
         }
 
@@ -370,3 +829,45 @@ impl<P: PhaseItem> RenderCommand<P> for DrawMeshInstanced {
         RenderCommandResult::Success
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The angle of `angle`, computed exactly from the mantissa and exponent of `turns`.
+    fn exact_angle(prime: u32, turns: f64) -> u32 {
+        let bits = turns.to_bits();
+        let exponent = ((bits >> 52) & 0x7ff) as i32;
+        let mantissa = (bits & ((1 << 52) - 1)) | 1 << 52;
+        // `turns` is `mantissa / 2^shift`
+        let shift = 1075 - exponent;
+        let product = prime as u128 * mantissa as u128;
+        match shift {
+            ..=32 => (product << (32 - shift)) as u32,
+            33..=159 => (product >> (shift - 32)) as u32,
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn angle_is_exact_near_u32_max() {
+        for turns in [
+            1.0 / std::f64::consts::TAU,
+            1.0 / 44.0,
+            0.618_033_988_749_895,
+            1e-7,
+            123.456,
+        ] {
+            for prime in (u32::MAX - 100_000..=u32::MAX).chain(0..1000) {
+                let error = angle(prime, turns).wrapping_sub(exact_angle(prime, turns));
+                // Off by at most one unit, in either direction around the full turn
+                assert!(
+                    error.min(error.wrapping_neg()) <= 1,
+                    "{prime} * {turns}: {} instead of {}",
+                    angle(prime, turns),
+                    exact_angle(prime, turns),
+                );
+            }
+        }
+    }
+}
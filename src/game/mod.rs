@@ -1,9 +1,31 @@
+mod console;
+mod culling;
+mod density;
+#[cfg(target_arch = "wasm32")]
+mod embed;
+mod fit;
+mod goto;
 mod instanced;
+mod orbit;
+mod playback;
+mod share;
+mod style;
+mod touch;
+mod transition;
 
-use crate::{assets::GameAssets, camera::GameCameraBundle, AppState, Args};
+use crate::{
+    assets::{GameAssets, LoadingProgress},
+    camera::{GameCamera, GameCameraBundle},
+    settings::{Layout, PointStyle, RadiusLaw, RenderMode, Settings, MAX_SPEED},
+    AppState, Args,
+};
 use bevy::{
+    core_pipeline::bloom::BloomSettings,
+    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
+    math::DVec2,
     prelude::*,
     render::{
+        camera::CameraUpdateSystem,
         render_asset::RenderAssetUsages,
         render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages},
         texture::BevyDefault,
@@ -15,7 +37,14 @@ use bevy_headless_render::{
     render_assets::HeadlessRenderSource,
 };
 use instanced::InstanceMaterialData;
+use orbit::Orbit;
+use playback::Playback;
 use std::{fs, time::Duration};
+use style::UserStyle;
+use transition::Transition;
+
+/// Go-to expressions, also taken by `--goto` and links.
+pub(crate) use goto::evaluate;
 
 pub struct GamePlugin;
 
@@ -27,8 +56,27 @@ impl Plugin for GamePlugin {
 
         app.add_systems(
             Update,
-            (game_time, zoom)
+            (game_time, pan.run_if(not(layout_is_3d)), zoom, layout)
+                .chain()
                 .run_if(mode_is_run)
+                .run_if(not(console::is_open))
+                .run_if(not(resource_exists::<Playback>))
+                .run_if(in_state(AppState::Game)),
+        );
+        app.add_systems(
+            Update,
+            (playback::play.run_if(mode_is_run), playback::apply)
+                .chain()
+                .before(transition::transition)
+                .run_if(resource_exists::<Playback>)
+                .run_if(in_state(AppState::Game)),
+        );
+        app.add_systems(
+            Update,
+            orbit::orbit
+                .run_if(mode_is_run)
+                .run_if(not(console::is_open))
+                .run_if(layout_is_3d)
                 .run_if(in_state(AppState::Game)),
         );
         app.add_systems(
@@ -37,19 +85,81 @@ impl Plugin for GamePlugin {
                 .run_if(mode_is_screenshot)
                 .run_if(in_state(AppState::Game)),
         );
+        app.add_systems(
+            Update,
+            (
+                stream_primes,
+                update_point_style,
+                update_highlights,
+                update_glow,
+            )
+                .run_if(in_state(AppState::Game)),
+        );
+        app.add_systems(
+            Update,
+            transition::transition
+                .after(game_time)
+                .after(layout)
+                .run_if(in_state(AppState::Game)),
+        );
+        app.add_systems(
+            Update,
+            update_angles
+                .after(transition::transition)
+                .run_if(in_state(AppState::Game)),
+        );
+        app.add_systems(
+            Update,
+            fit::fit
+                .after(stream_primes)
+                .after(transition::transition)
+                .run_if(resource_exists::<fit::Fit>)
+                .run_if(in_state(AppState::Game)),
+        );
+        #[cfg(target_arch = "wasm32")]
+        app.add_systems(
+            Update,
+            (
+                share::sync,
+                (embed::apply_requests, embed::hover).after(transition::transition),
+                embed::resolve_captures,
+            )
+                .run_if(mode_is_run)
+                .run_if(in_state(AppState::Game)),
+        );
+        app.add_systems(
+            PostUpdate,
+            culling::cull_instances
+                .after(CameraUpdateSystem)
+                .run_if(in_state(AppState::Game)),
+        );
 
-        app.add_plugins(instanced::InstancedPlugin);
+        app.add_plugins((
+            instanced::InstancedPlugin,
+            density::DensityPlugin,
+            style::StylePlugin,
+            goto::GoToPlugin,
+            console::ConsolePlugin,
+            touch::TouchPlugin,
+        ));
     }
 }
 
 fn mode_is_run(args: Res<Args>) -> bool {
-    matches!(*args, Args::Run)
+    matches!(*args, Args::Run { .. })
 }
 
 fn mode_is_screenshot(args: Res<Args>) -> bool {
     matches!(*args, Args::Screenshot { .. })
 }
 
+fn layout_is_3d(settings: Res<Settings>) -> bool {
+    settings.layout.is_3d()
+}
+
+/// Largest speed of the game time, `MAX_SPEED` with the fastest speed-up key.
+const MAX_GAME_SPEED: f64 = MAX_SPEED as f64 * 16.0;
+
 #[derive(Debug, Resource)]
 struct GameTime {
     elapsed: Duration,
@@ -67,19 +177,35 @@ impl Default for GameTime {
     }
 }
 
-fn game_time(time: Res<Time>, mut game_time: ResMut<GameTime>, input: Res<ButtonInput<KeyCode>>) {
+/// Limits a speed of the game time to `0..=MAX_GAME_SPEED`, as presets and timelines set speeds
+/// unchecked.
+fn limit_speed(speed: f64) -> f64 {
+    match speed {
+        speed if speed.is_nan() => 0.0,
+        speed => speed.clamp(0.0, MAX_GAME_SPEED),
+    }
+}
+
+fn game_time(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut game_time: ResMut<GameTime>,
+    input: Res<ButtonInput<KeyCode>>,
+) {
     let game_time = &mut *game_time;
 
-    game_time.speed_target = match () {
-        _ if input.pressed(KeyCode::ShiftLeft) => 16.0,
-        _ if input.pressed(KeyCode::ControlLeft) => 4.0,
-        _ => 1.0,
-    };
-    game_time.speed_current = f64::lerp(
+    game_time.speed_target = settings.view.speed as f64
+        * match () {
+            _ if input.pressed(KeyCode::ShiftLeft) => 16.0,
+            _ if input.pressed(KeyCode::ControlLeft) => 4.0,
+            _ => 1.0,
+        };
+    game_time.speed_target = limit_speed(game_time.speed_target);
+    game_time.speed_current = limit_speed(f64::lerp(
         game_time.speed_current,
         game_time.speed_target,
         1.0 - f64::exp(f64::ln(0.95) * 60.0 * time.delta_seconds_f64()),
-    );
+    ));
 
     if input.pressed(KeyCode::KeyR) {
         game_time.elapsed = Duration::ZERO;
@@ -110,10 +236,12 @@ impl Default for Zoom {
 }
 
 fn zoom(
+    mut commands: Commands,
     time: Res<Time>,
+    settings: Res<Settings>,
     mut zoom: ResMut<Zoom>,
     input: Res<ButtonInput<KeyCode>>,
-    mut camera: Query<&mut OrthographicProjection>,
+    mut camera: Query<&mut OrthographicProjection, With<GameCamera>>,
 ) {
     let Ok(mut projection) = camera.get_single_mut() else {
         return;
@@ -121,10 +249,18 @@ fn zoom(
 
     zoom.target = match () {
         _ if input.just_pressed(KeyCode::Space) => 0.0,
-        _ if input.just_pressed(KeyCode::ArrowUp) => f32::min(10.0, zoom.target + 1.0),
-        _ if input.just_pressed(KeyCode::ArrowDown) => f32::max(-6.0, zoom.target - 1.0),
+        _ if input.just_pressed(KeyCode::ArrowUp) => zoom.target + 1.0,
+        _ if input.just_pressed(KeyCode::ArrowDown) => zoom.target - 1.0,
         _ => zoom.target,
     };
+    // Limits that have been crossed leave the zoom at the lower one
+    zoom.target = f32::max(
+        settings.view.zoom_min,
+        f32::min(settings.view.zoom_max, zoom.target),
+    );
+    if input.just_pressed(KeyCode::KeyF) {
+        commands.init_resource::<fit::Fit>();
+    }
     zoom.current = f32::lerp(
         zoom.current,
         zoom.target,
@@ -134,28 +270,104 @@ fn zoom(
     projection.scale = zoom.scale();
 }
 
+/// Center of the view in world space. The game camera stays at the origin and the primes are
+/// placed relative to the center instead, in double precision, so the view can zoom deep into the
+/// plot far from the origin.
+#[derive(Debug, Default, Resource)]
+struct ViewCenter(DVec2);
+
+/// Zoom levels per line the mouse wheel is scrolled.
+const WHEEL_ZOOM_PER_LINE: f32 = 0.25;
+
+/// Pixels per line, for mouse wheels that scroll by pixels.
+const WHEEL_PIXELS_PER_LINE: f32 = 100.0;
+
+fn pan(
+    mut commands: Commands,
+    mut view_center: ResMut<ViewCenter>,
+    mut zoom: ResMut<Zoom>,
+    input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    mut mouse_wheel: EventReader<MouseWheel>,
+    camera: Query<(&Camera, &OrthographicProjection), With<GameCamera>>,
+) {
+    let Ok((camera, projection)) = camera.get_single() else {
+        return;
+    };
+    let Some(viewport_size) = camera.logical_viewport_size() else {
+        return;
+    };
+
+    // Drag the plot along with the mouse, which ends a flight to a number
+    let world_per_pixel = projection.area.height() as f64 / viewport_size.y as f64;
+    for motion in mouse_motion.read() {
+        if mouse_input.pressed(MouseButton::Left) {
+            commands.remove_resource::<goto::Flight>();
+            view_center.0 -=
+                DVec2::new(motion.delta.x as f64, -motion.delta.y as f64) * world_per_pixel;
+        }
+    }
+    for wheel in mouse_wheel.read() {
+        let lines = match wheel.unit {
+            MouseScrollUnit::Line => wheel.y,
+            MouseScrollUnit::Pixel => wheel.y / WHEEL_PIXELS_PER_LINE,
+        };
+        zoom.target += lines * WHEEL_ZOOM_PER_LINE;
+    }
+
+    if input.just_pressed(KeyCode::Space) {
+        commands.remove_resource::<goto::Flight>();
+        view_center.0 = DVec2::ZERO;
+    }
+}
+
+fn layout(mut settings: ResMut<Settings>, input: Res<ButtonInput<KeyCode>>) {
+    if input.just_pressed(KeyCode::KeyL) {
+        settings.layout = settings.layout.next();
+    }
+    if input.just_pressed(KeyCode::KeyM) {
+        settings.radius_law = settings.radius_law.next();
+    }
+}
+
+/// Frames to wait after the view changed, until it has been rendered into the destination image.
+const SETTLE_FRAMES: u32 = 2;
+
 fn save_screenshot(
     instances: Query<&InstanceMaterialData>,
-    mut wait_one_frame: Local<bool>,
+    fit: Option<Res<fit::Fit>>,
+    mut settled_frames: Local<u32>,
+    mut frame: Local<u32>,
     destination: Query<&HeadlessRenderDestination>,
     args: Res<Args>,
+    settings: Res<Settings>,
+    user_style: Res<UserStyle>,
+    mut playback: Option<ResMut<Playback>>,
+    mut game_time: ResMut<GameTime>,
     mut app_exit: EventWriter<AppExit>,
 ) {
     let Args::Screenshot {
         width,
         height,
-        game_time,
+        game_time: start_time,
         game_zoom_exp,
+        fit: fit_view,
+        fps,
+        ..
     } = &*args
     else {
         return;
     };
 
-    if instances.iter().any(|instance| !instance.has_rendered()) {
+    if instances.iter().any(|instance| !instance.has_rendered())
+        || user_style.is_pending()
+        || fit.is_some()
+    {
         return;
     }
-    if !*wait_one_frame {
-        *wait_one_frame = true;
+    if *settled_frames < SETTLE_FRAMES {
+        *settled_frames += 1;
         return;
     }
 
@@ -171,40 +383,180 @@ fn save_screenshot(
         Ok(image) => image.to_rgba8(),
         Err(e) => panic!("Failed to create image buffer {e:?}"),
     };
-    let image_path = format!(
-        "./screenshots/primes_{}x{}_{}_{}.png",
-        width,
-        height,
-        game_time.as_millis(),
-        game_zoom_exp,
-    );
+    let (image_dir, image_path) = match playback {
+        Some(_) => {
+            let image_dir = format!("./screenshots/timeline_{}x{}", width, height);
+            let image_path = format!("{}/frame_{:05}.png", image_dir, *frame);
+            (image_dir, image_path)
+        }
+        None => {
+            let image_path = format!(
+                "./screenshots/primes_{}x{}_{}_{}{}{}{}.png",
+                width,
+                height,
+                start_time.as_millis(),
+                match fit_view {
+                    true => "fit".to_string(),
+                    false => game_zoom_exp.to_string(),
+                },
+                match settings.layout {
+                    Layout::Polar => "",
+                    Layout::Cylinder => "_cylinder",
+                    Layout::Helix => "_helix",
+                },
+                match settings.radius_law {
+                    RadiusLaw::Linear => "",
+                    RadiusLaw::Sqrt => "_sqrt",
+                    RadiusLaw::Log => "_log",
+                    RadiusLaw::PrimeOverLog => "_prime_over_log",
+                },
+                match settings.render_mode {
+                    RenderMode::Points => "",
+                    RenderMode::Density => "_density",
+                },
+            );
+            ("./screenshots".to_string(), image_path)
+        }
+    };
 
-    fs::create_dir_all("./screenshots").unwrap();
+    fs::create_dir_all(image_dir).unwrap();
     if let Err(e) = image.save(image_path) {
         panic!("Failed to save image: {}", e);
     };
 
+    // Render the timeline frame by frame, at exact points in time
+    if let Some(playback) = &mut playback {
+        *frame += 1;
+        playback.seek(*frame as f32 / *fps as f32, &mut game_time);
+        if !playback.is_finished() {
+            *settled_frames = 0;
+            return;
+        }
+    }
+
     app_exit.send(AppExit::Success);
 }
 
+/// The layer of all primes, styled by `Settings::points`.
+#[derive(Debug, Component)]
+struct PrimesLayer;
+
+/// Appends the primes read since the last frame, so they appear while they are still loading.
+fn stream_primes(
+    loading_progress: Res<LoadingProgress>,
+    mut generation: Local<Option<u64>>,
+    mut layers: Query<&mut InstanceMaterialData, With<PrimesLayer>>,
+) {
+    let (chunks_generation, chunks) = loading_progress.take_chunks();
+    let restarted = generation.replace(chunks_generation) != Some(chunks_generation);
+    if chunks.is_empty() && !restarted {
+        return;
+    }
+
+    for mut instances in &mut layers {
+        if restarted {
+            instances.clear();
+        }
+        for chunk in &chunks {
+            instances.extend(chunk.iter().copied());
+        }
+    }
+}
+
+fn update_point_style(
+    settings: Res<Settings>,
+    mut layers: Query<&mut PointStyle, With<PrimesLayer>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    for mut point_style in &mut layers {
+        point_style.set_if_neq(settings.points);
+    }
+}
+
+/// Highlights the primes of the settings and the prime gone to.
+fn update_highlights(
+    settings: Res<Settings>,
+    goto: Res<goto::GoTo>,
+    mut layers: Query<&mut InstanceMaterialData, With<PrimesLayer>>,
+) {
+    if !settings.is_changed() && !goto.is_changed() {
+        return;
+    }
+
+    let mut highlights = settings.highlights.clone();
+    highlights.extend(goto.highlight());
+    for mut instances in &mut layers {
+        instances.set_highlights(&highlights);
+    }
+}
+
+/// Recomputes the angles of the primes when the angle multiplier changes, while it is swept or
+/// blended by a transition.
+fn update_angles(
+    transition: Res<Transition>,
+    game_time: Res<GameTime>,
+    mut layers: Query<&mut InstanceMaterialData, With<PrimesLayer>>,
+) {
+    let angle_multiplier = transition
+        .animation
+        .angle_multiplier_at(game_time.elapsed.as_secs_f64());
+    for mut instances in &mut layers {
+        instances.set_angle_multiplier(angle_multiplier);
+    }
+}
+
+fn update_glow(settings: Res<Settings>, mut bloom: Query<&mut BloomSettings, With<GameCamera>>) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    for mut bloom in &mut bloom {
+        bloom.intensity = settings.glow.intensity;
+        bloom.prefilter_settings.threshold = settings.glow.threshold;
+    }
+}
+
 fn setup(
     mut commands: Commands,
     assets: Res<GameAssets>,
+    settings: Res<Settings>,
     mut images: ResMut<Assets<Image>>,
     headless_render_sources: Option<ResMut<Assets<HeadlessRenderSource>>>,
     args: Res<Args>,
 ) {
     match &*args {
-        Args::Run => {
-            commands.spawn((GameCameraBundle::default(), StateScoped(AppState::Game)));
-            commands.init_resource::<GameTime>();
-            commands.init_resource::<Zoom>();
+        Args::Run {
+            game_time,
+            game_zoom_exp,
+            pan,
+            ..
+        } => {
+            let zoom = Zoom {
+                current: *game_zoom_exp,
+                target: *game_zoom_exp,
+            };
+            let mut game_camera_bundle = GameCameraBundle::default();
+            game_camera_bundle.camera.projection.scale = zoom.scale();
+            commands.spawn((game_camera_bundle, StateScoped(AppState::Game)));
+            commands.insert_resource(GameTime {
+                elapsed: *game_time,
+                ..default()
+            });
+            commands.insert_resource(zoom);
+            commands.insert_resource(ViewCenter(*pan));
         }
         Args::Screenshot {
             width,
             height,
             game_time,
             game_zoom_exp,
+            pan,
+            fit,
+            fit_radius,
+            ..
         } => {
             let game_time = GameTime {
                 elapsed: *game_time,
@@ -212,8 +564,8 @@ fn setup(
                 speed_target: 1.0,
             };
             let zoom = Zoom {
-                current: *game_zoom_exp as f32,
-                target: *game_zoom_exp as f32,
+                current: *game_zoom_exp,
+                target: *game_zoom_exp,
             };
 
             let mut image = Image::new_fill(
@@ -249,18 +601,44 @@ fn setup(
 
             commands.insert_resource(game_time);
             commands.insert_resource(zoom);
+            commands.insert_resource(ViewCenter(*pan));
+            if *fit {
+                commands.insert_resource(fit::Fit {
+                    radius: *fit_radius,
+                });
+            }
         }
     }
+    if let Some(timeline) = args.timeline() {
+        let playback = Playback::new(timeline.clone());
+        commands.insert_resource(GameTime {
+            elapsed: playback.start_time(),
+            ..default()
+        });
+        commands.insert_resource(playback);
+    }
+    commands.init_resource::<Orbit>();
+    commands.init_resource::<ViewCenter>();
+    commands.insert_resource(Transition::new(&settings));
 
     commands.spawn((
         assets.circle.clone(),
         SpatialBundle::INHERITED_IDENTITY,
-        InstanceMaterialData::from_iter(assets.primes.primes().iter().copied()),
+        // Filled by `stream_primes`
+        InstanceMaterialData::default(),
+        settings.points,
+        PrimesLayer,
+        // Instances are culled by `culling::cull_instances` instead
         NoFrustumCulling,
+        StateScoped(AppState::Game),
     ));
 }
 
 fn cleanup(mut commands: Commands) {
     commands.remove_resource::<GameTime>();
     commands.remove_resource::<Zoom>();
+    commands.remove_resource::<Orbit>();
+    commands.remove_resource::<ViewCenter>();
+    commands.remove_resource::<Transition>();
+    commands.remove_resource::<Playback>();
 }
@@ -0,0 +1,69 @@
+use bevy::{input::mouse::MouseMotion, prelude::*};
+use std::f32::consts::FRAC_PI_2;
+
+/// Distance of the orbit camera from its target at zoom level 0, matching the height of the view
+/// of the game camera.
+pub const DISTANCE: f32 = 100.0;
+
+/// Vertical field of view of the orbit camera in radians.
+pub const FOV: f32 = std::f32::consts::FRAC_PI_4;
+
+/// Rotation in radians per pixel the mouse is dragged.
+const DRAG_SENSITIVITY: f32 = 0.005;
+
+/// Rotation in radians per second while an arrow key is held.
+const KEY_SPEED: f32 = 1.0;
+
+/// Camera of the 3D layouts, orbiting the axis of the layout. Its distance is given by `Zoom`.
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct Orbit {
+    /// Rotation around the axis.
+    pub yaw: f32,
+    /// Elevation above the plane of the polar layout.
+    pub pitch: f32,
+}
+
+impl Default for Orbit {
+    fn default() -> Self {
+        Self {
+            yaw: 0.0,
+            pitch: 0.3,
+        }
+    }
+}
+
+impl Orbit {
+    pub fn view_from_world(&self, zoom_scale: f32) -> Mat4 {
+        let rotation = Quat::from_euler(EulerRot::YXZ, self.yaw, -self.pitch, 0.0);
+        let eye = rotation * Vec3::new(0.0, 0.0, DISTANCE * zoom_scale);
+        Mat4::look_at_rh(eye, Vec3::ZERO, Vec3::Y)
+    }
+
+    pub fn clip_from_view(aspect_ratio: f32) -> Mat4 {
+        Mat4::perspective_infinite_reverse_rh(FOV, aspect_ratio, 0.1)
+    }
+}
+
+pub fn orbit(
+    time: Res<Time>,
+    mut orbit: ResMut<Orbit>,
+    input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+) {
+    let mut delta = Vec2::ZERO;
+    for motion in mouse_motion.read() {
+        if mouse_input.pressed(MouseButton::Left) {
+            delta += motion.delta * DRAG_SENSITIVITY;
+        }
+    }
+    if input.pressed(KeyCode::ArrowLeft) {
+        delta.x -= KEY_SPEED * time.delta_seconds();
+    }
+    if input.pressed(KeyCode::ArrowRight) {
+        delta.x += KEY_SPEED * time.delta_seconds();
+    }
+
+    orbit.yaw -= delta.x;
+    orbit.pitch = f32::clamp(orbit.pitch + delta.y, -FRAC_PI_2 + 0.01, FRAC_PI_2 - 0.01);
+}
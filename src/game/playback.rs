@@ -0,0 +1,103 @@
+use super::{transition::Transition, GameTime, ViewCenter, Zoom};
+use crate::{camera::GameCamera, settings::Settings, timeline::Timeline};
+use bevy::prelude::*;
+use std::time::Duration;
+
+/// A timeline being played back. Takes over the controls of the game time, zoom and layout.
+#[derive(Debug, Resource)]
+pub struct Playback {
+    timeline: Timeline,
+    /// Seconds since the start of the timeline.
+    at: f32,
+}
+
+impl Playback {
+    pub fn new(timeline: Timeline) -> Self {
+        Self { timeline, at: 0.0 }
+    }
+
+    pub fn start_time(&self) -> Duration {
+        Duration::from_secs_f64(f64::max(0.0, self.timeline.start_time()))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.at > self.timeline.duration()
+    }
+
+    /// Moves forward to `at`, advancing the game time along the timeline.
+    pub fn seek(&mut self, at: f32, game_time: &mut GameTime) {
+        let elapsed = self
+            .timeline
+            .advance(game_time.elapsed.as_secs_f64(), self.at, at);
+        game_time.elapsed = Duration::from_secs_f64(f64::max(0.0, elapsed));
+        self.at = at;
+    }
+}
+
+/// Plays the timeline back in real time, and hands the controls back at its end.
+pub fn play(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut playback: ResMut<Playback>,
+    mut game_time: ResMut<GameTime>,
+) {
+    let at = playback.at + time.delta_seconds();
+    playback.seek(at, &mut game_time);
+
+    if playback.is_finished() {
+        commands.remove_resource::<Playback>();
+    }
+}
+
+/// Applies the tracks of the timeline at the current point to the view.
+pub fn apply(
+    playback: Res<Playback>,
+    mut game_time: ResMut<GameTime>,
+    mut zoom: ResMut<Zoom>,
+    mut settings: ResMut<Settings>,
+    mut transition: ResMut<Transition>,
+    mut view_center: ResMut<ViewCenter>,
+    mut camera: Query<&mut OrthographicProjection, With<GameCamera>>,
+) {
+    let sample = playback.timeline.sample(playback.at);
+
+    if let Some(speed) = sample.speed {
+        game_time.speed_current = speed;
+        game_time.speed_target = speed;
+    }
+    if let Some(value) = sample.zoom {
+        zoom.current = value;
+        zoom.target = value;
+    }
+    for mut projection in &mut camera {
+        projection.scale = zoom.scale();
+    }
+    if let Some(pan) = sample.pan {
+        view_center.0 = pan.as_dvec2();
+    }
+
+    // Only touch the settings on a change, as every change of them is handled as an edit
+    if let Some(layout) = sample.layout.filter(|layout| *layout != settings.layout) {
+        settings.layout = layout;
+    }
+    if let Some(radius_law) = sample
+        .radius_law
+        .filter(|radius_law| *radius_law != settings.radius_law)
+    {
+        settings.radius_law = radius_law;
+    }
+    if let Some(render_mode) = sample
+        .render_mode
+        .filter(|render_mode| *render_mode != settings.render_mode)
+    {
+        settings.render_mode = render_mode;
+    }
+    if let Some(angle_multiplier) = sample
+        .angle_multiplier
+        .filter(|angle_multiplier| *angle_multiplier != settings.animation.angle_multiplier)
+    {
+        // The track is interpolated already, blending it again would lag behind
+        settings.animation.angle_multiplier = angle_multiplier;
+        transition.snap_animation(settings.animation);
+    }
+}
@@ -0,0 +1,51 @@
+use super::{goto::GoTo, GameTime, ViewCenter, Zoom};
+use crate::{link::ViewLink, settings::Settings};
+#[cfg(target_arch = "wasm32")]
+use bevy::prelude::*;
+
+/// The current view, as a link opens it.
+pub fn view_link(
+    settings: &Settings,
+    game_time: &GameTime,
+    zoom: &Zoom,
+    view_center: &ViewCenter,
+    goto: &GoTo,
+) -> ViewLink {
+    ViewLink {
+        time: game_time.elapsed,
+        zoom: zoom.target,
+        pan: view_center.0,
+        goto: goto.target(),
+        settings: settings.clone(),
+    }
+}
+
+/// Seconds between updates of the address of the page, as browsers limit them.
+#[cfg(target_arch = "wasm32")]
+const SYNC_INTERVAL: f32 = 1.0;
+
+/// Keeps the fragment of the address of the page on the current view, so it can be shared or
+/// bookmarked.
+#[cfg(target_arch = "wasm32")]
+pub fn sync(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    game_time: Res<GameTime>,
+    zoom: Res<Zoom>,
+    view_center: Res<ViewCenter>,
+    goto: Res<GoTo>,
+    mut since_sync: Local<f32>,
+    mut synced: Local<String>,
+) {
+    *since_sync += time.delta_seconds();
+    if *since_sync < SYNC_INTERVAL {
+        return;
+    }
+    *since_sync = 0.0;
+
+    let fragment = view_link(&settings, &game_time, &zoom, &view_center, &goto).fragment();
+    if fragment != *synced {
+        crate::link::replace_fragment(&fragment);
+        *synced = fragment;
+    }
+}
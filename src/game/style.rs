@@ -0,0 +1,205 @@
+use crate::{settings::Settings, AppState};
+use bevy::{
+    asset::{io::Reader, AssetLoadFailedEvent, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    render::{
+        extract_resource::{ExtractResource, ExtractResourcePlugin},
+        render_resource::{CachedPipelineState, PipelineCacheError},
+    },
+};
+use std::sync::{Arc, Mutex};
+
+/// Imports available to user styles, prepended to the snippet. Mirrors the first import of
+/// `shader.wgsl`.
+const STYLE_PRELUDE: &str = "#import primes::instanced::{PI, globals, gamma_function, zoom_scale, radius, instance_angle, Vertex, VertexOutput, vertex_output, fragment_output}\n";
+
+/// Entry points calling the `position`, `scale` and `color` functions of a user style, appended to
+/// the snippet. Mirrors the end of `shader.wgsl`.
+const STYLE_ENTRY_POINTS: &str = r#"
+@vertex
+fn vertex(vertex: Vertex, @builtin(instance_index) index: u32) -> VertexOutput {
+    let prime = vertex.i_prime;
+    let angle = instance_angle(vertex);
+    return vertex_output(vertex, position(prime, angle, index), scale(prime, angle, index), color(prime, angle, index));
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    return fragment_output(in);
+}
+"#;
+
+pub struct StylePlugin;
+
+impl Plugin for StylePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_asset_loader(StyleLoader)
+            .init_resource::<UserStyle>()
+            .add_plugins(ExtractResourcePlugin::<UserStyle>::default());
+
+        app.add_systems(OnEnter(AppState::Game), spawn_overlay);
+        app.add_systems(
+            Update,
+            (load_style, load_failed, update_overlay)
+                .chain()
+                .run_if(in_state(AppState::Game)),
+        );
+    }
+}
+
+/// Loads `*.style.wgsl` snippets as complete shaders, by wrapping them with the imports and entry
+/// points of the instanced pipeline.
+struct StyleLoader;
+
+impl AssetLoader for StyleLoader {
+    type Asset = Shader;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Shader, Self::Error> {
+        let mut snippet = String::new();
+        reader.read_to_string(&mut snippet).await?;
+
+        let source = format!("{STYLE_PRELUDE}{snippet}\n{STYLE_ENTRY_POINTS}");
+        let path = load_context
+            .asset_path()
+            .to_string()
+            .replace(std::path::MAIN_SEPARATOR, "/");
+        Ok(Shader::from_wgsl(source, path))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["style.wgsl"]
+    }
+}
+
+/// The user style selected in the settings, if any, and the state of its pipelines.
+#[derive(Debug, Clone, Default, Resource, ExtractResource)]
+pub struct UserStyle {
+    path: Option<String>,
+    shader: Option<Handle<Shader>>,
+    status: Arc<Mutex<StyleStatus>>,
+}
+
+#[derive(Debug, Default)]
+struct StyleStatus {
+    /// The pipelines of the style are still being compiled.
+    pending: bool,
+    /// The last load or compile error of the style, shown in the overlay.
+    error: Option<String>,
+}
+
+impl UserStyle {
+    pub fn shader(&self) -> Option<AssetId<Shader>> {
+        self.shader.as_ref().map(Handle::id)
+    }
+
+    /// Whether the style is still compiling, so the default style is drawn in its place.
+    pub fn is_pending(&self) -> bool {
+        self.shader.is_some() && self.status.lock().unwrap().pending
+    }
+
+    /// Records the state of a pipeline using the style and returns whether it can be used.
+    pub fn update_status(&self, state: &CachedPipelineState) -> bool {
+        let mut status = self.status.lock().unwrap();
+        match state {
+            CachedPipelineState::Ok(_) => {
+                status.pending = false;
+                status.error = None;
+                true
+            }
+            CachedPipelineState::Queued
+            | CachedPipelineState::Creating(_)
+            | CachedPipelineState::Err(
+                PipelineCacheError::ShaderNotLoaded(_)
+                | PipelineCacheError::ShaderImportNotYetAvailable,
+            ) => false,
+            CachedPipelineState::Err(err) => {
+                status.pending = false;
+                status.error = Some(err.to_string());
+                false
+            }
+        }
+    }
+}
+
+fn load_style(
+    settings: Res<Settings>,
+    mut user_style: ResMut<UserStyle>,
+    asset_server: Res<AssetServer>,
+) {
+    if !settings.is_changed() || settings.style == user_style.path {
+        return;
+    }
+
+    *user_style = UserStyle {
+        path: settings.style.clone(),
+        shader: settings.style.as_ref().map(|path| asset_server.load(path)),
+        status: Arc::new(Mutex::new(StyleStatus {
+            pending: true,
+            error: None,
+        })),
+    };
+}
+
+/// Reports a style that could not be read, such as a missing file. Its pipelines would wait for the
+/// shader forever.
+fn load_failed(user_style: Res<UserStyle>, mut events: EventReader<AssetLoadFailedEvent<Shader>>) {
+    for event in events.read() {
+        if user_style.shader() == Some(event.id) {
+            let mut status = user_style.status.lock().unwrap();
+            status.pending = false;
+            status.error = Some(event.error.to_string());
+        }
+    }
+}
+
+#[derive(Debug, Component)]
+struct StyleErrorOverlay;
+
+fn spawn_overlay(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                color: Color::srgb(1.0, 0.3, 0.3),
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            left: Val::Px(10.0),
+            max_width: Val::Percent(60.0),
+            ..default()
+        }),
+        StyleErrorOverlay,
+        StateScoped(AppState::Game),
+    ));
+}
+
+fn update_overlay(
+    user_style: Res<UserStyle>,
+    mut overlay: Query<&mut Text, With<StyleErrorOverlay>>,
+) {
+    let Ok(mut text) = overlay.get_single_mut() else {
+        return;
+    };
+
+    let status = user_style.status.lock().unwrap();
+    let value = match (&user_style.path, &status.error) {
+        (Some(path), Some(error)) => {
+            format!("Failed to load style {path}, using the default style:\n{error}")
+        }
+        _ => String::new(),
+    };
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
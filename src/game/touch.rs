@@ -0,0 +1,207 @@
+use super::{goto, mode_is_run, share, GameTime, ViewCenter, Zoom};
+use crate::{full_screen, settings::Settings, AppState};
+use bevy::{
+    input::touch::TouchInput,
+    prelude::*,
+    window::{PrimaryWindow, WindowResized},
+};
+use std::time::Duration;
+
+/// A bar of buttons for what the keys do, shown once the screen is touched.
+pub struct TouchPlugin;
+
+impl Plugin for TouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Game), setup.run_if(mode_is_run));
+        app.add_systems(
+            Update,
+            (show, arrange, press, update_speed)
+                .chain()
+                .run_if(mode_is_run)
+                .run_if(in_state(AppState::Game)),
+        );
+    }
+}
+
+/// Speeds the speed button cycles through, like the speed-up keys.
+const SPEEDS: [f32; 3] = [1.0, 4.0, 16.0];
+
+/// Size of the buttons in logical pixels, large enough to be hit with a finger.
+const BUTTON_SIZE: f32 = 48.0;
+
+const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+const BUTTON_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.1);
+
+#[derive(Debug, Component)]
+struct TouchBar;
+
+#[derive(Debug, Clone, Copy, Component)]
+enum TouchButton {
+    ZoomOut,
+    ZoomIn,
+    Speed,
+    Reset,
+    FullScreen,
+    Fps,
+    Link,
+}
+
+/// Marks the label of the speed button, which shows the speed.
+#[derive(Debug, Component)]
+struct SpeedLabel;
+
+fn setup(mut commands: Commands) {
+    let text_style = TextStyle {
+        font_size: 16.0,
+        color: TEXT_COLOR,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(10.0),
+                    bottom: Val::Px(10.0),
+                    padding: UiRect::all(Val::Px(6.0)),
+                    row_gap: Val::Px(6.0),
+                    column_gap: Val::Px(6.0),
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.7).into(),
+                // Shown by `show` on the first touch
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            TouchBar,
+            StateScoped(AppState::Game),
+        ))
+        .with_children(|parent| {
+            for (button, label) in [
+                (TouchButton::ZoomOut, "-"),
+                (TouchButton::ZoomIn, "+"),
+                (TouchButton::Speed, ""),
+                (TouchButton::Reset, "Reset"),
+                (TouchButton::FullScreen, "Full"),
+                (TouchButton::Fps, "FPS"),
+                (TouchButton::Link, "Link"),
+            ] {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                min_width: Val::Px(BUTTON_SIZE),
+                                height: Val::Px(BUTTON_SIZE),
+                                padding: UiRect::horizontal(Val::Px(6.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: BUTTON_COLOR.into(),
+                            ..default()
+                        },
+                        button,
+                    ))
+                    .with_children(|parent| {
+                        let mut label =
+                            parent.spawn(TextBundle::from_section(label, text_style.clone()));
+                        if let TouchButton::Speed = button {
+                            label.insert(SpeedLabel);
+                        }
+                    });
+            }
+        });
+}
+
+/// Shows the bar on touch screens, once they are touched.
+fn show(mut touches: EventReader<TouchInput>, mut bar: Query<&mut Visibility, With<TouchBar>>) {
+    if touches.is_empty() {
+        return;
+    }
+    touches.clear();
+
+    for mut visibility in &mut bar {
+        *visibility = Visibility::Inherited;
+    }
+}
+
+/// Lays the bar out along the shorter side of the window, so it keeps clear of the plot when the
+/// device is rotated.
+fn arrange(
+    mut resized: EventReader<WindowResized>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut bar: Query<(Ref<TouchBar>, &mut Style)>,
+) {
+    let resized = resized.read().count() > 0;
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+
+    for (bar, mut style) in &mut bar {
+        if !resized && !bar.is_added() {
+            continue;
+        }
+        style.flex_direction = match window.width() < window.height() {
+            true => FlexDirection::Column,
+            false => FlexDirection::Row,
+        };
+    }
+}
+
+fn press(
+    mut commands: Commands,
+    mut settings: ResMut<Settings>,
+    mut game_time: ResMut<GameTime>,
+    mut zoom: ResMut<Zoom>,
+    mut view_center: ResMut<ViewCenter>,
+    goto: Res<goto::GoTo>,
+    mut window: Query<&mut Window, With<PrimaryWindow>>,
+    buttons: Query<(&Interaction, &TouchButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match button {
+            TouchButton::ZoomOut => zoom.target -= 1.0,
+            TouchButton::ZoomIn => zoom.target += 1.0,
+            TouchButton::Speed => {
+                settings.view.speed = SPEEDS
+                    .into_iter()
+                    .find(|speed| *speed > settings.view.speed)
+                    .unwrap_or(SPEEDS[0]);
+            }
+            TouchButton::Reset => {
+                // Like both `Space` and `R`
+                commands.remove_resource::<goto::Flight>();
+                zoom.target = 0.0;
+                view_center.0 = default();
+                game_time.elapsed = Duration::ZERO;
+            }
+            TouchButton::FullScreen => {
+                if let Ok(mut window) = window.get_single_mut() {
+                    full_screen::toggle(&mut window);
+                }
+            }
+            TouchButton::Fps => settings.hud.fps = !settings.hud.fps,
+            TouchButton::Link => {
+                // Like the `link` command of the console
+                let link = share::view_link(&settings, &game_time, &zoom, &view_center, &goto);
+                let url = crate::link::url(&link.fragment());
+                #[cfg(target_arch = "wasm32")]
+                crate::link::copy(&url);
+                info!("Link to the view: {url}");
+            }
+        }
+    }
+}
+
+fn update_speed(settings: Res<Settings>, mut label: Query<(Ref<SpeedLabel>, &mut Text)>) {
+    for (label, mut text) in &mut label {
+        if settings.is_changed() || label.is_added() {
+            text.sections[0].value = format!("{}x", settings.view.speed);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use super::GameTime;
+use crate::settings::{AnimationSettings, Layout, RadiusLaw, Settings};
+use bevy::prelude::*;
+use std::time::Duration;
+
+/// Blends from the previous layout, radius law and animation to the ones in `Settings`. Runs on game time, so
+/// screenshots of a transition are deterministic.
+#[derive(Debug, Resource)]
+pub struct Transition {
+    pub from_layout: Layout,
+    pub from_radius_law: RadiusLaw,
+    from_animation: AnimationSettings,
+    to_layout: Layout,
+    to_radius_law: RadiusLaw,
+    to_animation: AnimationSettings,
+    start: Duration,
+    /// Eased progress of the transition at the current game time.
+    pub progress: f32,
+    /// The animation constants blended at `progress`.
+    pub animation: AnimationSettings,
+}
+
+impl Transition {
+    /// A finished transition to the given settings.
+    pub fn new(settings: &Settings) -> Self {
+        Self {
+            from_layout: settings.layout,
+            from_radius_law: settings.radius_law,
+            from_animation: settings.animation,
+            to_layout: settings.layout,
+            to_radius_law: settings.radius_law,
+            to_animation: settings.animation,
+            start: Duration::ZERO,
+            progress: 1.0,
+            animation: settings.animation,
+        }
+    }
+
+    /// Jumps to the given animation without blending, for values that are interpolated already,
+    /// like the tracks of a timeline.
+    pub fn snap_animation(&mut self, animation: AnimationSettings) {
+        self.from_animation = animation;
+        self.to_animation = animation;
+        self.animation = animation;
+    }
+
+    /// Whether the primes are placed by a 3D layout at any point of the transition.
+    pub fn is_3d(&self) -> bool {
+        self.to_layout.is_3d() || (self.progress < 1.0 && self.from_layout.is_3d())
+    }
+
+    /// The radius laws the radii are blended between, the same one twice if there is no
+    /// transition.
+    pub fn radius_laws(&self) -> [RadiusLaw; 2] {
+        match self.progress < 1.0 {
+            true => [self.from_radius_law, self.to_radius_law],
+            false => [self.to_radius_law; 2],
+        }
+    }
+}
+
+pub fn transition(
+    settings: Res<Settings>,
+    game_time: Res<GameTime>,
+    mut transition: ResMut<Transition>,
+) {
+    let transition = &mut *transition;
+
+    if settings.layout != transition.to_layout
+        || settings.radius_law != transition.to_radius_law
+        || settings.animation != transition.to_animation
+    {
+        // An interrupted transition continues from where it is, the layout and radius law can only
+        // be blended between two of them though
+        if transition.progress >= 0.5 {
+            transition.from_layout = transition.to_layout;
+            transition.from_radius_law = transition.to_radius_law;
+        }
+        transition.from_animation = transition.animation;
+        transition.to_layout = settings.layout;
+        transition.to_radius_law = settings.radius_law;
+        transition.to_animation = settings.animation;
+        transition.start = game_time.elapsed;
+    }
+
+    let duration = settings.transition.duration;
+    let t = match game_time.elapsed.checked_sub(transition.start) {
+        Some(elapsed) if duration > 0.0 => elapsed.as_secs_f32() / duration,
+        // Time was reset to before the start
+        _ => 1.0,
+    };
+    transition.progress = settings.transition.easing.ease(t);
+    transition.animation = transition
+        .from_animation
+        .lerp(&transition.to_animation, transition.progress);
+}
@@ -0,0 +1,28 @@
+use crate::primes::{Format, Primes};
+use bevy::app::AppExit;
+use std::path::Path;
+
+/// Sieves the primes up to `max` and writes them to `out` in the given format.
+pub fn run(max: u32, format: Format, out: &Path) -> AppExit {
+    let primes = Primes::build_with_progress(max, |done, total| {
+        crate::print_progress("Sieving", done, total)
+    });
+
+    if let Some(parent) = out.parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            eprintln!("Failed to create {}: {}", parent.display(), e);
+            return AppExit::error();
+        }
+    }
+    if let Err(e) = std::fs::write(out, primes.encode(format)) {
+        eprintln!("Failed to write {}: {}", out.display(), e);
+        return AppExit::error();
+    }
+    eprintln!(
+        "Wrote {} primes to {}",
+        primes.primes().len(),
+        out.display()
+    );
+
+    AppExit::Success
+}
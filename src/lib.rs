@@ -5,50 +5,281 @@ mod camera;
 mod dev;
 mod full_screen;
 mod game;
+mod generate;
+mod link;
+pub mod number_theory;
 mod primes;
+mod settings;
 mod splash_screen;
+mod stats;
+mod timeline;
+
+pub use primes::{Format, Primes, Sieve};
+pub use settings::Settings;
+pub use stats::{li, Comparison, Gap, GapStats, Residues, Stats};
+pub use timeline::Timeline;
 
 use bevy::{
     app::{RunMode, ScheduleRunnerPlugin},
+    math::DVec2,
     prelude::*,
     winit::WinitPlugin,
 };
 use bevy_asset_loader::loading_state::{LoadingState, LoadingStateAppExt};
-use std::time::Duration;
+use std::{path::PathBuf, time::Duration};
+
+/// Asset path of the primes written by the build script, plotted unless `--primes` tells otherwise.
+const PRIMES_PATH: &str = "primes.bin";
+
+/// What the binary was started for, the app or one of the subcommands without a window.
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)] // only one is ever made
+pub enum Command {
+    App(Args),
+    Stats {
+        /// File of primes, in the format given by its extension.
+        primes: PathBuf,
+        /// Sieve the primes up to this bound instead of reading them.
+        sieve: Option<u32>,
+        /// Only primes up to this bound are considered.
+        max: Option<u32>,
+        modulus: u32,
+        json: bool,
+    },
+    Generate {
+        max: u32,
+        format: Format,
+        out: PathBuf,
+    },
+}
+
+impl Command {
+    #[cfg(target_arch = "wasm32")]
+    pub fn from_env() -> Result<Self, pico_args::Error> {
+        Ok(Self::App(Args::from_env()))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_env() -> Result<Self, pico_args::Error> {
+        let mut args = pico_args::Arguments::from_env();
+        Ok(match args.subcommand().unwrap().as_deref() {
+            Some("stats") => Self::Stats {
+                primes: args
+                    .value_from_str("--primes")
+                    .unwrap_or_else(|_| "assets/primes.bin".into()),
+                sieve: args.opt_value_from_str("--sieve")?,
+                max: args.opt_value_from_str("--max")?,
+                modulus: args.opt_value_from_str("--modulus")?.unwrap_or(44),
+                json: args.contains("--json"),
+            },
+            Some("generate") => {
+                // Invalid values are errors rather than defaults, which would overwrite `primes.bin`
+                let format = args.opt_value_from_str("--format")?.unwrap_or(Format::Raw);
+                Self::Generate {
+                    max: args.opt_value_from_str("--max")?.unwrap_or(5_000_000),
+                    format,
+                    out: args
+                        .value_from_str("--out")
+                        .unwrap_or_else(|_| format!("assets/primes.{}", format.extension()).into()),
+                }
+            }
+            subcommand => Self::App(Args::from_args(subcommand, &mut args)?),
+        })
+    }
+
+    pub fn run(self) -> AppExit {
+        match self {
+            Self::App(args) => build_app(args).run(),
+            Self::Stats {
+                primes,
+                sieve,
+                max,
+                modulus,
+                json,
+            } => stats::run(&primes, sieve, max, modulus, json),
+            Self::Generate { max, format, out } => generate::run(max, format, &out),
+        }
+    }
+}
 
 #[derive(Debug, Resource)]
 pub enum Args {
-    Run,
+    Run {
+        game_time: Duration,
+        game_zoom_exp: f32,
+        /// Center of the view in world space.
+        pan: DVec2,
+        /// Number to fly to, as with the `goto` command of the console.
+        goto: Option<u64>,
+        /// Asset path of the file of primes to plot.
+        primes: String,
+        settings: Settings,
+        timeline: Option<Timeline>,
+    },
     Screenshot {
         width: u32,
         height: u32,
         game_time: Duration,
-        game_zoom_exp: i32,
+        game_zoom_exp: f32,
+        /// Center of the view in world space.
+        pan: DVec2,
+        /// Frame all primes, or the disc of radius `fit_radius`, instead of zooming to
+        /// `game_zoom_exp`.
+        fit: bool,
+        fit_radius: Option<f64>,
+        /// Frames per second the timeline is rendered with.
+        fps: u32,
+        /// Asset path of the file of primes to plot.
+        primes: String,
+        settings: Settings,
+        timeline: Option<Timeline>,
     },
 }
 
 impl Args {
+    /// The app as opened by the address of the page, see `ViewLink`.
     #[cfg(target_arch = "wasm32")]
     pub fn from_env() -> Self {
-        Self::Run
+        let link = link::ViewLink::from_location();
+        Self::Run {
+            game_time: link.time,
+            game_zoom_exp: link.zoom,
+            pan: link.pan,
+            goto: link.goto,
+            primes: PRIMES_PATH.into(),
+            settings: link.settings,
+            timeline: None,
+        }
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    pub fn from_env() -> Self {
-        let mut args = pico_args::Arguments::from_env();
-        match args.subcommand().unwrap().as_deref() {
-            Some("run") | None => Self::Run,
-            Some("screenshot") => Self::Screenshot {
-                width: args.value_from_str("--width").unwrap_or(1920),
-                height: args.value_from_str("--height").unwrap_or(1080),
-                game_time: Duration::from_millis(args.value_from_str("--time").unwrap_or(0)),
-                game_zoom_exp: args.value_from_str("--zoom").unwrap_or(0),
-            },
+    pub fn from_args(
+        subcommand: Option<&str>,
+        args: &mut pico_args::Arguments,
+    ) -> Result<Self, pico_args::Error> {
+        Ok(match subcommand {
+            Some("run") | None => {
+                let primes = primes_from_args(args);
+                let mut link = link::ViewLink {
+                    time: Duration::from_millis(args.value_from_str("--time").unwrap_or(0)),
+                    zoom: args.value_from_str("--zoom").unwrap_or(0.0),
+                    pan: args.value_from_fn("--pan", parse_pan).unwrap_or_default(),
+                    goto: args.opt_value_from_fn("--goto", game::evaluate)?,
+                    settings: Settings::from_args(args)?,
+                };
+                // The parameters of a link take precedence over the other arguments
+                if let Some(url) = args.opt_value_from_str::<_, String>("--link")? {
+                    link.apply_url(&url);
+                }
+                Self::Run {
+                    game_time: link.time,
+                    game_zoom_exp: link.zoom,
+                    pan: link.pan,
+                    goto: link.goto,
+                    primes,
+                    settings: link.settings,
+                    timeline: timeline_from_args(args)?,
+                }
+            }
+            Some("screenshot") => {
+                let fit_radius = args.opt_value_from_fn("--fit-radius", parse_fit_radius)?;
+                Self::Screenshot {
+                    width: args.value_from_str("--width").unwrap_or(1920),
+                    height: args.value_from_str("--height").unwrap_or(1080),
+                    game_time: Duration::from_millis(args.value_from_str("--time").unwrap_or(0)),
+                    game_zoom_exp: args.value_from_str("--zoom").unwrap_or(0.0),
+                    pan: args.value_from_fn("--pan", parse_pan).unwrap_or_default(),
+                    fit: args.contains("--fit") || fit_radius.is_some(),
+                    fit_radius,
+                    fps: args.opt_value_from_fn("--fps", parse_fps)?.unwrap_or(30),
+                    primes: primes_from_args(args),
+                    settings: Settings::from_args(args)?,
+                    timeline: timeline_from_args(args)?,
+                }
+            }
             _ => panic!("Invalid subcommand"),
+        })
+    }
+
+    pub fn settings(&self) -> &Settings {
+        match self {
+            Self::Run { settings, .. } => settings,
+            Self::Screenshot { settings, .. } => settings,
+        }
+    }
+
+    pub fn primes(&self) -> &str {
+        match self {
+            Self::Run { primes, .. } => primes,
+            Self::Screenshot { primes, .. } => primes,
+        }
+    }
+
+    pub fn timeline(&self) -> Option<&Timeline> {
+        match self {
+            Self::Run { timeline, .. } => timeline.as_ref(),
+            Self::Screenshot { timeline, .. } => timeline.as_ref(),
         }
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+fn primes_from_args(args: &mut pico_args::Arguments) -> String {
+    args.value_from_str("--primes")
+        .unwrap_or_else(|_| PRIMES_PATH.into())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn timeline_from_args(
+    args: &mut pico_args::Arguments,
+) -> Result<Option<Timeline>, pico_args::Error> {
+    args.opt_value_from_fn("--timeline", |path: &str| {
+        Timeline::load(path.as_ref()).map_err(|e| format!("failed to load timeline: {e}"))
+    })
+}
+
+/// Parses a point in world space, given as `x,y`.
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_pan(s: &str) -> Result<DVec2, String> {
+    let invalid = || format!("invalid pan: {s}");
+    let (x, y) = s.split_once(',').ok_or_else(invalid)?;
+    Ok(DVec2::new(
+        x.trim().parse().map_err(|_| invalid())?,
+        y.trim().parse().map_err(|_| invalid())?,
+    ))
+}
+
+/// Parses a frame rate, which must not be zero.
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_fps(s: &str) -> Result<u32, String> {
+    match s.parse() {
+        Ok(0) => Err("the frame rate must be at least 1".into()),
+        Ok(fps) => Ok(fps),
+        Err(_) => Err(format!("invalid frame rate: {s}")),
+    }
+}
+
+/// Parses the radius of the disc to frame, which must be positive.
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_fit_radius(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(radius) if radius > 0.0 && radius.is_finite() => Ok(radius),
+        _ => Err(format!("invalid fit radius: {s}")),
+    }
+}
+
+/// Shows the progress of a subcommand on stderr, as a percentage updated in place.
+#[cfg(not(target_arch = "wasm32"))]
+fn print_progress(label: &str, done: usize, total: usize) {
+    let percent = |done: usize| done * 100 / usize::max(1, total);
+    if done == 1 || percent(done) != percent(done - 1) {
+        eprint!("\r{label}... {}%", percent(done));
+    }
+    if done == total {
+        eprintln!();
+    }
+}
+
 pub fn build_app(args: Args) -> App {
     let mut app = App::new();
 
@@ -58,7 +289,7 @@ pub fn build_app(args: Args) -> App {
         ..default()
     });
     let default_plugins = match &args {
-        Args::Run => default_plugins.set(WindowPlugin {
+        Args::Run { .. } => default_plugins.set(WindowPlugin {
             primary_window: Some(Window {
                 fit_canvas_to_parent: true,
                 present_mode: bevy::window::PresentMode::AutoNoVsync,
@@ -73,7 +304,7 @@ pub fn build_app(args: Args) -> App {
         .insert_resource(ClearColor(Color::srgb(0.02, 0.02, 0.02)));
 
     match &args {
-        Args::Run => (),
+        Args::Run { .. } => (),
         Args::Screenshot { .. } => {
             app.add_plugins((
                 ScheduleRunnerPlugin {
@@ -84,11 +315,13 @@ pub fn build_app(args: Args) -> App {
         }
     }
 
+    app.insert_resource(args.settings().clone());
     app.insert_resource(args);
 
     app.init_state::<AppState>()
         .enable_state_scoped_entities::<AppState>()
-        .init_state::<AssetsState>();
+        .init_state::<AssetsState>()
+        .enable_state_scoped_entities::<AssetsState>();
 
     app.add_loading_state({
         LoadingState::new(AssetsState::Loading)
@@ -101,6 +334,7 @@ pub fn build_app(args: Args) -> App {
         full_screen::FullScreenPlugin,
         splash_screen::SplashScreenPlugin,
         game::GamePlugin,
+        settings::SettingsPanelPlugin,
         dev::DevPlugin,
     ));
 
@@ -0,0 +1,194 @@
+use crate::{
+    game,
+    settings::{checked_speed, parse_angle, Settings},
+};
+use bevy::{log::warn, math::DVec2};
+use std::{f64::consts::TAU, time::Duration};
+
+/// Address of the web build, which links made outside of it point to.
+#[cfg(not(target_arch = "wasm32"))]
+const DEMO_URL: &str = "https://jannik4.github.io/primes/";
+
+/// A view of the plot as given by the query or fragment of a link to the web build, such as
+/// `?t=5600&z=4&layout=polar&mod=44`.
+#[derive(Debug, Clone, Default)]
+pub struct ViewLink {
+    pub time: Duration,
+    pub zoom: f32,
+    pub pan: DVec2,
+    /// Number gone to, as with the `goto` command.
+    pub goto: Option<u64>,
+    pub settings: Settings,
+}
+
+impl ViewLink {
+    /// The view of the address of the page, with the fragment overriding the query.
+    #[cfg(target_arch = "wasm32")]
+    pub fn from_location() -> Self {
+        let mut link = Self::default();
+        if let Some(window) = web_sys::window() {
+            let location = window.location();
+            link.apply(&location.search().unwrap_or_default());
+            link.apply(&location.hash().unwrap_or_default());
+        }
+        link
+    }
+
+    /// Applies the parameters of the query and fragment of `url`, such as one of the `link`
+    /// command of the console.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn apply_url(&mut self, url: &str) {
+        let (url, fragment) = url.split_once('#').unwrap_or((url, ""));
+        if let Some((_, query)) = url.split_once('?') {
+            self.apply(query);
+        }
+        self.apply(fragment);
+    }
+
+    /// Applies the parameters of a query or fragment, with or without its leading `?` or `#`.
+    /// Invalid parameters are skipped.
+    pub fn apply(&mut self, params: &str) {
+        let params = params.trim_start_matches(['?', '#']);
+        for param in params.split('&').filter(|param| !param.is_empty()) {
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+            if let Err(e) = self.set(key, &decode(value)) {
+                warn!("Skipping link parameter {key}: {e}");
+            }
+        }
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let number = || match value.parse::<f64>() {
+            Ok(number) if number.is_finite() => Ok(number),
+            _ => Err(format!("invalid number: {value}")),
+        };
+        match key {
+            "t" => self.time = Duration::from_millis(value.parse().map_err(|_| "invalid time")?),
+            "z" => self.zoom = number()? as f32,
+            "x" => self.pan.x = number()?,
+            "y" => self.pan.y = number()?,
+            "speed" => self.settings.view.speed = checked_speed(number()?)?,
+            "layout" => self.settings.layout = value.parse()?,
+            "radius" => self.settings.radius_law = value.parse()?,
+            "render" => self.settings.render_mode = value.parse()?,
+            "mod" => match value.parse::<u32>() {
+                Ok(modulus) if modulus > 0 => {
+                    self.settings.animation.angle_multiplier = TAU / modulus as f64;
+                }
+                _ => return Err(format!("invalid modulus: {value}")),
+            },
+            "a" => self.settings.animation.angle_multiplier = parse_angle(value)?,
+            "n" => self.goto = Some(game::evaluate(value)?),
+            _ => return Err("unknown parameter".into()),
+        }
+        Ok(())
+    }
+
+    /// The fragment of a link to this view, without the `#`. Settings at their default are left
+    /// out, to keep links short.
+    pub fn fragment(&self) -> String {
+        let default = Settings::default();
+        let settings = &self.settings;
+
+        let mut params = vec![
+            format!("t={}", self.time.as_millis()),
+            // Hundredths of a zoom level are not told apart
+            format!("z={}", (self.zoom * 100.0).round() / 100.0),
+        ];
+        if self.pan != DVec2::ZERO {
+            params.push(format!("x={}&y={}", self.pan.x, self.pan.y));
+        }
+        if settings.view.speed != default.view.speed {
+            params.push(format!("speed={}", settings.view.speed));
+        }
+        if settings.layout != default.layout {
+            params.push(format!("layout={}", settings.layout));
+        }
+        if settings.radius_law != default.radius_law {
+            params.push(format!("radius={}", settings.radius_law));
+        }
+        if settings.render_mode != default.render_mode {
+            params.push(format!("render={}", settings.render_mode));
+        }
+        let angle = settings.animation.angle_multiplier;
+        if angle != default.animation.angle_multiplier {
+            // Multipliers set with `mod` are linked as such, as they are rounded in radians
+            let modulus = (TAU / angle).round();
+            match modulus >= 1.0 && (TAU / modulus - angle).abs() < 1e-12 {
+                true => params.push(format!("mod={modulus}")),
+                false => params.push(format!("a={angle}")),
+            }
+        }
+        if let Some(goto) = self.goto {
+            params.push(format!("n={goto}"));
+        }
+        params.join("&")
+    }
+}
+
+/// Decodes the `%XX` escapes of a URL, which browsers put in for characters such as `^`.
+fn decode(s: &str) -> String {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut rest = s.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = tail
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (byte, escaped) {
+            (b'%', Some(escaped)) => {
+                bytes.push(escaped);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Full address of a link to the view of `fragment`, on the page itself.
+#[cfg(target_arch = "wasm32")]
+pub fn url(fragment: &str) -> String {
+    let Some(window) = web_sys::window() else {
+        return format!("#{fragment}");
+    };
+    let location = window.location();
+    format!(
+        "{}{}#{fragment}",
+        location.origin().unwrap_or_default(),
+        location.pathname().unwrap_or_default(),
+    )
+}
+
+/// Full address of a link to the view of `fragment`, on the live demo.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn url(fragment: &str) -> String {
+    format!("{DEMO_URL}#{fragment}")
+}
+
+/// Shows `fragment` in the address of the page, without adding an entry to the history.
+#[cfg(target_arch = "wasm32")]
+pub fn replace_fragment(fragment: &str) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    if let Ok(history) = window.history() {
+        let url = format!("#{fragment}");
+        if let Err(e) = history.replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&url))
+        {
+            warn!("Failed to update the address: {e:?}");
+        }
+    }
+}
+
+/// Copies `text` to the clipboard. Browsers only allow it right after an input of the user.
+#[cfg(target_arch = "wasm32")]
+pub fn copy(text: &str) {
+    if let Some(window) = web_sys::window() {
+        // The promise settles on its own, a refused copy is reported by the browser
+        let _ = window.navigator().clipboard().write_text(text);
+    }
+}
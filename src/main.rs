@@ -3,5 +3,11 @@
 use bevy::app::AppExit;
 
 fn main() -> AppExit {
-    primes::build_app(primes::Args::from_env()).run()
+    match primes::Command::from_env() {
+        Ok(command) => command.run(),
+        Err(e) => {
+            eprintln!("Invalid arguments: {e}");
+            AppExit::error()
+        }
+    }
 }
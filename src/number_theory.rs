@@ -0,0 +1,215 @@
+#![allow(dead_code)] // module is used in this crate and in the build script
+
+//! Primality and factorization of arbitrary `u64`s, independent of a table of primes.
+
+/// Bases for which Miller–Rabin is deterministic for all `u64`.
+const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/// Trial division is used for factors up to this bound, before Pollard's rho.
+const TRIAL_DIVISION_BOUND: u64 = 1000;
+
+/// Deterministic Miller–Rabin test.
+pub fn is_prime(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+    for p in WITNESSES {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    WITNESSES.iter().all(|&a| {
+        let mut x = pow_mod(a, d, n);
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+/// The smallest prime greater than `n`, `None` if it does not fit a `u64`.
+pub fn next_prime(n: u64) -> Option<u64> {
+    if n < 2 {
+        return Some(2);
+    }
+    // Only odd candidates, starting after `n`
+    let start = n.checked_add(1 + n % 2)?;
+    (start..=u64::MAX).step_by(2).find(|c| is_prime(*c))
+}
+
+/// The largest prime less than `n`.
+pub fn prev_prime(n: u64) -> Option<u64> {
+    (2..n).rev().find(|c| is_prime(*c))
+}
+
+/// The prime factors of `n` in ascending order, repeated by their multiplicity. Empty for `0` and
+/// `1`.
+pub fn factorize(mut n: u64) -> Vec<u64> {
+    let mut factors = Vec::new();
+    if n == 0 {
+        return factors;
+    }
+
+    let mut d = 2;
+    while d <= TRIAL_DIVISION_BOUND && d * d <= n {
+        while n.is_multiple_of(d) {
+            factors.push(d);
+            n /= d;
+        }
+        d += 1;
+    }
+
+    split(n, &mut factors);
+    factors.sort_unstable();
+    factors
+}
+
+/// Pushes the prime factors of `n`, which has no factors below the trial division bound.
+fn split(n: u64, factors: &mut Vec<u64>) {
+    if n == 1 {
+        return;
+    }
+    if is_prime(n) {
+        factors.push(n);
+        return;
+    }
+
+    let d = pollard_rho(n);
+    split(d, factors);
+    split(n / d, factors);
+}
+
+/// A non-trivial factor of the odd composite `n`.
+fn pollard_rho(n: u64) -> u64 {
+    for c in 1u64.. {
+        let f = |x: u64| ((mul_mod(x, x, n) as u128 + c as u128) % n as u128) as u64;
+        let (mut x, mut y, mut d) = (2, 2, 1);
+        while d == 1 {
+            x = f(x);
+            y = f(f(y));
+            d = gcd(x.abs_diff(y), n);
+        }
+        // The cycle closed without a factor, retry with another polynomial
+        if d != n {
+            return d;
+        }
+    }
+    unreachable!()
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    (a as u128 * b as u128 % m as u128) as u64
+}
+
+fn pow_mod(mut base: u64, mut exp: u64, m: u64) -> u64 {
+    let mut result = 1;
+    base %= m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primes::Primes;
+
+    /// Largest prime below 2^32.
+    const P32: u64 = 4_294_967_291;
+    /// Largest prime below 2^64.
+    const P64: u64 = 18_446_744_073_709_551_557;
+
+    #[test]
+    fn strong_pseudoprimes_are_composite() {
+        // Strong pseudoprimes to the bases 2, 3, 5, 7 and to all prime bases up to 23
+        assert!(!is_prime(3_215_031_751));
+        assert!(!is_prime(3_825_123_056_546_413_051));
+        assert_eq!(factorize(3_215_031_751), [151, 751, 28_351]);
+        assert_eq!(
+            factorize(3_825_123_056_546_413_051),
+            [149_491, 747_451, 34_233_211]
+        );
+    }
+
+    #[test]
+    fn carmichael_numbers_are_composite() {
+        for n in [561, 1105, 1729, 2465, 2821, 6601, 8911, 41_041, 825_265] {
+            assert!(!is_prime(n), "{n}");
+            assert!(factorize(n).len() >= 3, "{n}");
+        }
+    }
+
+    #[test]
+    fn semiprimes_near_u64_max() {
+        let q = 4_294_967_279;
+        assert!(is_prime(P32) && is_prime(q));
+        assert!(!is_prime(P32 * q));
+        assert_eq!(factorize(P32 * q), [q, P32]);
+        assert_eq!(factorize(P32 * P32), [P32, P32]);
+        // A factor just above the trial division bound
+        assert_eq!(
+            factorize(18_446_744_073_709_394_837),
+            [1009, 18_282_204_235_589_093]
+        );
+        assert!(is_prime(P64));
+        assert_eq!(factorize(P64), [P64]);
+    }
+
+    #[test]
+    fn factorize_round_trips() {
+        let large = (u64::MAX - 10_000..=u64::MAX).step_by(97);
+        for n in (1..20_000).chain(large) {
+            let factors = factorize(n);
+            assert!(factors.iter().all(|f| is_prime(*f)), "{n}: {factors:?}");
+            assert!(factors.is_sorted(), "{n}: {factors:?}");
+            assert_eq!(factors.iter().product::<u64>(), n);
+        }
+        assert!(factorize(0).is_empty());
+    }
+
+    #[test]
+    fn next_and_prev_prime_match_the_sieve() {
+        let primes = Primes::build(200_000);
+        for n in 0..200_000 {
+            assert_eq!(is_prime(n as u64), primes.contains(n), "{n}");
+            if let Some(next) = primes.next_prime(n) {
+                assert_eq!(next_prime(n as u64), Some(next as u64), "{n}");
+            }
+            assert_eq!(
+                prev_prime(n as u64),
+                primes.prev_prime(n).map(u64::from),
+                "{n}"
+            );
+        }
+    }
+
+    #[test]
+    fn next_and_prev_prime_at_the_ends() {
+        assert_eq!(next_prime(P64), None);
+        assert_eq!(next_prime(P64 - 1), Some(P64));
+        assert_eq!(prev_prime(u64::MAX), Some(P64));
+        assert_eq!(prev_prime(2), None);
+        assert_eq!(next_prime(P32), Some(1 << 32 | 15));
+    }
+}
@@ -1,5 +1,17 @@
 #![allow(dead_code)] // module is used in this crate and in the build script
 
+use crate::number_theory::is_prime;
+use std::{
+    error::Error,
+    ops::Range,
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+/// Numbers per segment of the sieve, small enough for a segment to stay in the cache.
+const SEGMENT_SIZE: u64 = 1 << 18;
+
 #[derive(Debug, Clone)]
 pub struct Primes {
     primes: Vec<u32>,
@@ -7,21 +19,320 @@ pub struct Primes {
 
 impl Primes {
     pub fn build(max: u32) -> Self {
-        Self {
-            primes: (0..=max).filter(|n| is_prime(*n)).collect(),
+        Self::build_with_progress(max, |_, _| ())
+    }
+
+    /// Sieves the segments on all cores. `progress` is called with the number of sieved segments
+    /// and the number of all segments, from the sieving threads.
+    pub fn build_with_progress(max: u32, progress: impl Fn(usize, usize) + Sync) -> Self {
+        let sieve = Sieve::new(max);
+        let segments = sieve.segments().collect::<Vec<_>>();
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+
+        let next = AtomicUsize::new(0);
+        let done = AtomicUsize::new(0);
+        let work = || {
+            let mut sieved = Vec::new();
+            loop {
+                let idx = next.fetch_add(1, Ordering::Relaxed);
+                let Some(segment) = segments.get(idx) else {
+                    break sieved;
+                };
+                sieved.push((idx, sieve.sieve_segment(segment.clone())));
+                progress(done.fetch_add(1, Ordering::Relaxed) + 1, segments.len());
+            }
+        };
+
+        let mut results = vec![Vec::new(); segments.len()];
+        let mut collect = |sieved: Vec<(usize, Vec<u32>)>| {
+            for (idx, primes) in sieved {
+                results[idx] = primes;
+            }
+        };
+        if threads > 1 {
+            thread::scope(|scope| {
+                let handles = (0..threads).map(|_| scope.spawn(work)).collect::<Vec<_>>();
+                for handle in handles {
+                    collect(handle.join().unwrap());
+                }
+            });
+        } else {
+            collect(work());
         }
+
+        Sieve::merge(results)
     }
 
     pub fn from_unchecked(primes: Vec<u32>) -> Self {
         Self { primes }
     }
 
+    /// Reads primes written by `encode` in the given format.
+    pub fn decode(bytes: &[u8], format: Format) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut primes = Vec::new();
+        let mut decoder = Decoder::new(format);
+        decoder.decode(bytes, &mut primes)?;
+        decoder.finish(&mut primes)?;
+        Ok(Self::from_unchecked(primes))
+    }
+
+    pub fn encode(&self, format: Format) -> Vec<u8> {
+        match format {
+            Format::Raw => self.primes.iter().flat_map(|p| p.to_ne_bytes()).collect(),
+            Format::Gap => {
+                let mut bytes = Vec::with_capacity(self.primes.len());
+                let mut previous = 0;
+                for prime in &self.primes {
+                    let mut gap = prime - previous;
+                    previous = *prime;
+                    while gap >= 0x80 {
+                        bytes.push((gap & 0x7f) as u8 | 0x80);
+                        gap >>= 7;
+                    }
+                    bytes.push(gap as u8);
+                }
+                bytes
+            }
+            Format::Text => self
+                .primes
+                .iter()
+                .flat_map(|p| format!("{p}\n").into_bytes())
+                .collect(),
+        }
+    }
+
     pub fn primes(&self) -> &[u32] {
         &self.primes
     }
+
+    /// Whether `n` is in the table.
+    pub fn contains(&self, n: u32) -> bool {
+        self.primes.binary_search(&n).is_ok()
+    }
+
+    /// The `n`-th prime in the table, counting from `nth_prime(1) == Some(2)`.
+    pub fn nth_prime(&self, n: usize) -> Option<u32> {
+        self.primes.get(n.checked_sub(1)?).copied()
+    }
+
+    /// Number of primes less than or equal to `x`.
+    pub fn prime_pi(&self, x: u32) -> usize {
+        self.primes.partition_point(|p| *p <= x)
+    }
+
+    /// The smallest prime in the table greater than `n`.
+    pub fn next_prime(&self, n: u32) -> Option<u32> {
+        self.primes.get(self.prime_pi(n)).copied()
+    }
+
+    /// The largest prime in the table less than `n`.
+    pub fn prev_prime(&self, n: u32) -> Option<u32> {
+        let idx = self.primes.partition_point(|p| *p < n);
+        self.primes.get(idx.checked_sub(1)?).copied()
+    }
+}
+
+/// Decodes primes in the given format from chunks of bytes split anywhere, so a file can be read
+/// piece by piece without holding all of its bytes.
+#[derive(Debug)]
+pub struct Decoder {
+    format: Format,
+    /// Bytes of a number split by the end of the last chunk.
+    partial: Vec<u8>,
+    /// The last prime, which the next gap is added to.
+    prime: u32,
+}
+
+impl Decoder {
+    pub fn new(format: Format) -> Self {
+        Self {
+            format,
+            partial: Vec::new(),
+            prime: 0,
+        }
+    }
+
+    /// Appends the primes completed by `bytes` to `primes`.
+    pub fn decode(
+        &mut self,
+        bytes: &[u8],
+        primes: &mut Vec<u32>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match self.format {
+            Format::Raw => {
+                const SIZE: usize = std::mem::size_of::<u32>();
+                let mut bytes = bytes;
+                if !self.partial.is_empty() {
+                    let missing = usize::min(SIZE - self.partial.len(), bytes.len());
+                    self.partial.extend_from_slice(&bytes[..missing]);
+                    bytes = &bytes[missing..];
+                    if self.partial.len() < SIZE {
+                        return Ok(());
+                    }
+                    primes.push(u32::from_ne_bytes(self.partial[..].try_into().unwrap()));
+                    self.partial.clear();
+                }
+                let chunks = bytes.chunks_exact(SIZE);
+                self.partial.extend_from_slice(chunks.remainder());
+                primes.extend(chunks.map(|chunk| u32::from_ne_bytes(chunk.try_into().unwrap())));
+            }
+            Format::Gap => {
+                for byte in bytes {
+                    if self.partial.len() > 4 {
+                        return Err("gap overflows".into());
+                    }
+                    self.partial.push(*byte);
+                    if byte & 0x80 == 0 {
+                        let gap = self
+                            .partial
+                            .iter()
+                            .enumerate()
+                            .fold(0u64, |gap, (i, byte)| {
+                                gap | ((byte & 0x7f) as u64) << (7 * i)
+                            });
+                        self.prime = u32::try_from(self.prime as u64 + gap)
+                            .map_err(|_| "prime overflows")?;
+                        primes.push(self.prime);
+                        self.partial.clear();
+                    }
+                }
+            }
+            Format::Text => {
+                let mut lines = bytes.split(|byte| *byte == b'\n');
+                // The last line continues in the next chunk, unless it is empty
+                let last = lines.next_back().unwrap_or_default();
+                for line in lines {
+                    self.partial.extend_from_slice(line);
+                    primes.push(parse_line(&self.partial)?);
+                    self.partial.clear();
+                }
+                self.partial.extend_from_slice(last);
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends a last line without a line break to `primes`, and checks that the bytes did not end
+    /// within a number.
+    pub fn finish(self, primes: &mut Vec<u32>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match self.format {
+            _ if self.partial.is_empty() => Ok(()),
+            Format::Raw => Err("length is not a multiple of 4".into()),
+            Format::Gap => Err("truncated gap".into()),
+            Format::Text => {
+                primes.push(parse_line(&self.partial)?);
+                Ok(())
+            }
+        }
+    }
+}
+
+fn parse_line(line: &[u8]) -> Result<u32, Box<dyn Error + Send + Sync>> {
+    let line = std::str::from_utf8(line)?;
+    Ok(line.strip_suffix('\r').unwrap_or(line).parse()?)
+}
+
+/// File formats of primes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Native endian `u32`s, the format of `primes.bin`.
+    Raw,
+    /// The gap to the previous prime (the first from zero) as LEB128 varint, mostly one byte per
+    /// prime.
+    Gap,
+    /// One decimal number per line.
+    Text,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Raw => "bin",
+            Self::Gap => "gaps",
+            Self::Text => "txt",
+        }
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "bin" => Some(Self::Raw),
+            "gaps" => Some(Self::Gap),
+            "txt" => Some(Self::Text),
+            _ => None,
+        }
+    }
+
+    /// The format of a file by its extension, `Raw` if it is unknown.
+    pub fn from_path(path: &std::path::Path) -> Self {
+        path.extension()
+            .and_then(|extension| Self::from_extension(extension.to_str()?))
+            .unwrap_or(Self::Raw)
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "raw" => Ok(Self::Raw),
+            "gap" => Ok(Self::Gap),
+            "text" => Ok(Self::Text),
+            _ => Err(format!("invalid format: {s}")),
+        }
+    }
+}
+
+/// A segmented sieve of Eratosthenes for the numbers `0..=max`, whose segments can be sieved
+/// independently and in any order.
+#[derive(Debug)]
+pub struct Sieve {
+    max: u32,
+    /// The primes up to the square root of `max`, which cross out all composites.
+    base: Vec<u32>,
 }
 
-fn is_prime(n: u32) -> bool {
-    let limit = (n as f64).sqrt() as u32;
-    n >= 2 && (2..=limit).all(|i| n % i != 0)
+impl Sieve {
+    pub fn new(max: u32) -> Self {
+        Self {
+            max,
+            base: (0..=max.isqrt()).filter(|n| is_prime(*n as u64)).collect(),
+        }
+    }
+
+    pub fn segments(&self) -> impl Iterator<Item = Range<u64>> {
+        let end = self.max as u64 + 1;
+        (0..end)
+            .step_by(SEGMENT_SIZE as usize)
+            .map(move |start| start..u64::min(start + SEGMENT_SIZE, end))
+    }
+
+    /// The primes in `segment`, in ascending order.
+    pub fn sieve_segment(&self, segment: Range<u64>) -> Vec<u32> {
+        let mut composite = vec![false; (segment.end - segment.start) as usize];
+        for p in self.base.iter().map(|p| *p as u64) {
+            if p * p >= segment.end {
+                break;
+            }
+            let first = u64::max(p * p, segment.start.div_ceil(p) * p);
+            for multiple in (first..segment.end).step_by(p as usize) {
+                composite[(multiple - segment.start) as usize] = true;
+            }
+        }
+
+        segment
+            .zip(composite)
+            .filter(|(n, composite)| *n >= 2 && !composite)
+            .map(|(n, _)| n as u32)
+            .collect()
+    }
+
+    /// Joins the primes of all segments, given in the order of `segments`.
+    pub fn merge(segments: Vec<Vec<u32>>) -> Primes {
+        let mut primes = Vec::with_capacity(segments.iter().map(Vec::len).sum());
+        for segment in segments {
+            primes.extend_from_slice(&segment);
+        }
+        Primes::from_unchecked(primes)
+    }
 }
@@ -0,0 +1,749 @@
+mod panel;
+
+use bevy::{prelude::*, render::extract_component::ExtractComponent};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+pub use panel::SettingsPanelPlugin;
+
+/// View settings shared by all modes. Initialized from the command line and changed at runtime.
+#[derive(Debug, Clone, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub render_mode: RenderMode,
+    pub layout: Layout,
+    pub radius_law: RadiusLaw,
+    pub density: DensitySettings,
+    pub animation: AnimationSettings,
+    pub transition: TransitionSettings,
+    pub points: PointStyle,
+    pub glow: GlowSettings,
+    /// Asset path of a `*.style.wgsl` snippet replacing the default style of the points.
+    pub style: Option<String>,
+    /// Primes drawn larger and brighter than the others.
+    pub highlights: Vec<u32>,
+    /// Only primes up to this bound are drawn.
+    pub bound: u32,
+    pub view: ViewSettings,
+    pub hud: HudSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            render_mode: RenderMode::default(),
+            layout: Layout::default(),
+            radius_law: RadiusLaw::default(),
+            density: DensitySettings::default(),
+            animation: AnimationSettings::default(),
+            transition: TransitionSettings::default(),
+            points: PointStyle::default(),
+            glow: GlowSettings::default(),
+            style: None,
+            highlights: Vec::new(),
+            bound: u32::MAX,
+            view: ViewSettings::default(),
+            hud: HudSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_args(args: &mut pico_args::Arguments) -> Result<Self, pico_args::Error> {
+        let default = args
+            .opt_value_from_fn("--preset", |path: &str| {
+                Self::load_preset(path.as_ref()).map_err(|e| format!("failed to load preset: {e}"))
+            })?
+            .unwrap_or_default();
+
+        Ok(Self {
+            render_mode: args
+                .value_from_str("--render")
+                .unwrap_or(default.render_mode),
+            layout: args.value_from_str("--layout").unwrap_or(default.layout),
+            radius_law: args
+                .value_from_str("--radius")
+                .unwrap_or(default.radius_law),
+            density: DensitySettings {
+                ramp: args
+                    .value_from_str("--ramp")
+                    .unwrap_or(default.density.ramp),
+                scale: args
+                    .value_from_str("--scale")
+                    .unwrap_or(default.density.scale),
+                saturation: args
+                    .value_from_str("--saturation")
+                    .unwrap_or(default.density.saturation),
+            },
+            animation: AnimationSettings {
+                pulse_frequency: args
+                    .value_from_str("--pulse-frequency")
+                    .unwrap_or(default.animation.pulse_frequency),
+                phase_factor: args
+                    .value_from_str("--phase-factor")
+                    .unwrap_or(default.animation.phase_factor),
+                rotation_rate: args
+                    .value_from_str("--rotation-rate")
+                    .unwrap_or(default.animation.rotation_rate),
+                radius_divisor: args
+                    .value_from_str("--radius-divisor")
+                    .unwrap_or(default.animation.radius_divisor),
+                zoom_base_in: args
+                    .value_from_str("--zoom-base-in")
+                    .unwrap_or(default.animation.zoom_base_in),
+                zoom_base_out: args
+                    .value_from_str("--zoom-base-out")
+                    .unwrap_or(default.animation.zoom_base_out),
+                angle_multiplier: args
+                    .value_from_fn("--angle-multiplier", parse_angle)
+                    .unwrap_or(default.animation.angle_multiplier),
+                angle_sweep: args
+                    .value_from_fn("--angle-sweep", parse_angle)
+                    .unwrap_or(default.animation.angle_sweep),
+                point_scale: args
+                    .value_from_str("--point-scale")
+                    .unwrap_or(default.animation.point_scale),
+            },
+            transition: TransitionSettings {
+                duration: args
+                    .value_from_str("--transition-duration")
+                    .unwrap_or(default.transition.duration),
+                easing: args
+                    .value_from_str("--easing")
+                    .unwrap_or(default.transition.easing),
+            },
+            points: PointStyle {
+                shape: args
+                    .value_from_str("--shape")
+                    .unwrap_or(default.points.shape),
+                size: args.value_from_str("--size").unwrap_or(default.points.size),
+            },
+            glow: GlowSettings {
+                intensity: args
+                    .value_from_str("--glow-intensity")
+                    .unwrap_or(default.glow.intensity),
+                threshold: args
+                    .value_from_str("--glow-threshold")
+                    .unwrap_or(default.glow.threshold),
+            },
+            style: args.opt_value_from_str("--style")?.or(default.style),
+            highlights: match args.values_from_str("--highlight")? {
+                highlights if highlights.is_empty() => default.highlights,
+                highlights => highlights,
+            },
+            bound: args.value_from_str("--bound").unwrap_or(default.bound),
+            view: ViewSettings {
+                speed: args
+                    .opt_value_from_fn("--speed", |speed: &str| {
+                        checked_speed(speed.parse().map_err(|e| format!("{e}"))?)
+                    })?
+                    .unwrap_or(default.view.speed),
+                zoom_min: args
+                    .value_from_str("--zoom-min")
+                    .unwrap_or(default.view.zoom_min),
+                zoom_max: args
+                    .value_from_str("--zoom-max")
+                    .unwrap_or(default.view.zoom_max),
+            },
+            hud: default.hud,
+        })
+    }
+
+    /// Loads a preset from a file, or from the standard input if `path` is `-`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_preset(
+        path: &std::path::Path,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let json = match path.to_str() {
+            Some("-") => std::io::read_to_string(std::io::stdin())?,
+            _ => std::fs::read_to_string(path)?,
+        };
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save_preset(
+        &self,
+        path: &std::path::Path,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderMode {
+    /// Every prime is drawn as a point.
+    #[default]
+    Points,
+    /// Primes are accumulated into a density texture, which is then mapped to colors.
+    Density,
+}
+
+impl RenderMode {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Points => Self::Density,
+            Self::Density => Self::Points,
+        }
+    }
+}
+
+impl FromStr for RenderMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "points" => Ok(Self::Points),
+            "density" => Ok(Self::Density),
+            _ => Err(format!("invalid render mode: {s}")),
+        }
+    }
+}
+
+impl fmt::Display for RenderMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Points => "points",
+            Self::Density => "density",
+        })
+    }
+}
+
+/// Where the primes are placed, mirroring `layout_position` in `instanced.wgsl`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    /// The angle and radius of the style are drawn as they are, in the plane.
+    #[default]
+    Polar,
+    /// The angle wraps around a cylinder and the radius becomes the height.
+    Cylinder,
+    /// The radius stays the distance from the axis and the angle becomes the height, winding up a
+    /// helix.
+    Helix,
+}
+
+impl Layout {
+    /// The 3D layouts are viewed through the orbit camera instead of the game camera.
+    pub fn is_3d(self) -> bool {
+        self != Self::Polar
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Polar => Self::Cylinder,
+            Self::Cylinder => Self::Helix,
+            Self::Helix => Self::Polar,
+        }
+    }
+}
+
+impl FromStr for Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "polar" => Ok(Self::Polar),
+            "cylinder" => Ok(Self::Cylinder),
+            "helix" => Ok(Self::Helix),
+            _ => Err(format!("invalid layout: {s}")),
+        }
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Polar => "polar",
+            Self::Cylinder => "cylinder",
+            Self::Helix => "helix",
+        })
+    }
+}
+
+/// How the radius of a prime grows with the prime, before it is divided by
+/// `AnimationSettings::radius_divisor`. Mirrors `radius_law` in `instanced.wgsl`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RadiusLaw {
+    /// `p`, which crowds the small primes into the center.
+    #[default]
+    Linear,
+    /// `32 √p`, which spreads the primes with an even density like the Sacks spiral.
+    Sqrt,
+    /// `512 ln p`.
+    Log,
+    /// `p / ln p`, which spreads the primes evenly along the radius.
+    PrimeOverLog,
+}
+
+impl RadiusLaw {
+    const SQRT_SCALE: f64 = 32.0;
+    const LOG_SCALE: f64 = 512.0;
+
+    pub fn radius(self, prime: f64) -> f64 {
+        match self {
+            Self::Linear => prime,
+            Self::Sqrt => Self::SQRT_SCALE * prime.sqrt(),
+            Self::Log => Self::LOG_SCALE * prime.ln(),
+            Self::PrimeOverLog => prime / prime.ln(),
+        }
+    }
+
+    /// The prime at `radius`, the inverse of `radius`. `p / ln p` falls below `e`, where its
+    /// smallest values lie, so zero is returned for radii up to the one of `2`.
+    pub fn prime(self, radius: f64) -> f64 {
+        match self {
+            Self::Linear => radius,
+            Self::Sqrt => (radius / Self::SQRT_SCALE).powi(2),
+            Self::Log => (radius / Self::LOG_SCALE).exp(),
+            Self::PrimeOverLog => {
+                if radius <= 2.0 / f64::ln(2.0) {
+                    return 0.0;
+                }
+                // Newton's method, starting from the first order approximation
+                let mut prime = radius * radius.ln();
+                for _ in 0..32 {
+                    let ln = prime.ln();
+                    let step = (prime / ln - radius) * ln * ln / (ln - 1.0);
+                    prime -= step;
+                    if step.abs() <= 1e-9 * prime {
+                        break;
+                    }
+                }
+                prime
+            }
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Linear => Self::Sqrt,
+            Self::Sqrt => Self::Log,
+            Self::Log => Self::PrimeOverLog,
+            Self::PrimeOverLog => Self::Linear,
+        }
+    }
+}
+
+impl FromStr for RadiusLaw {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linear" => Ok(Self::Linear),
+            "sqrt" => Ok(Self::Sqrt),
+            "log" => Ok(Self::Log),
+            "prime_over_log" => Ok(Self::PrimeOverLog),
+            _ => Err(format!("invalid radius law: {s}")),
+        }
+    }
+}
+
+impl fmt::Display for RadiusLaw {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Linear => "linear",
+            Self::Sqrt => "sqrt",
+            Self::Log => "log",
+            Self::PrimeOverLog => "prime_over_log",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DensitySettings {
+    pub ramp: ColorRamp,
+    pub scale: DensityScale,
+    /// Number of primes per pixel that is mapped to the top of the color ramp.
+    pub saturation: f32,
+}
+
+impl Default for DensitySettings {
+    fn default() -> Self {
+        Self {
+            ramp: ColorRamp::Inferno,
+            scale: DensityScale::Log,
+            saturation: 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorRamp {
+    Grayscale,
+    Viridis,
+    Inferno,
+    Magma,
+}
+
+impl ColorRamp {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Grayscale => Self::Viridis,
+            Self::Viridis => Self::Inferno,
+            Self::Inferno => Self::Magma,
+            Self::Magma => Self::Grayscale,
+        }
+    }
+}
+
+impl FromStr for ColorRamp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "grayscale" => Ok(Self::Grayscale),
+            "viridis" => Ok(Self::Viridis),
+            "inferno" => Ok(Self::Inferno),
+            "magma" => Ok(Self::Magma),
+            _ => Err(format!("invalid color ramp: {s}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DensityScale {
+    Linear,
+    Log,
+}
+
+impl FromStr for DensityScale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linear" => Ok(Self::Linear),
+            "log" => Ok(Self::Log),
+            _ => Err(format!("invalid density scale: {s}")),
+        }
+    }
+}
+
+/// Parses an angle in radians, or in the unit of a `deg` or `turn` suffix. The number may be a
+/// fraction like `1/3turn`, and `phi` is the turn divided by the golden ratio.
+pub(crate) fn parse_angle(s: &str) -> Result<f64, String> {
+    use std::f64::consts::TAU;
+
+    const PHI: f64 = 1.618_033_988_749_895;
+
+    let invalid = || format!("invalid angle: {s}");
+    if s == "phi" {
+        return Ok(TAU / PHI);
+    }
+    let (number, unit) = match s.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(idx) => s.split_at(idx),
+        None => (s, "rad"),
+    };
+    let number = match number.split_once('/') {
+        Some((numerator, denominator)) => {
+            let numerator: f64 = numerator.parse().map_err(|_| invalid())?;
+            let denominator: f64 = denominator.parse().map_err(|_| invalid())?;
+            numerator / denominator
+        }
+        None => number.parse().map_err(|_| invalid())?,
+    };
+    match unit {
+        "rad" => Ok(number),
+        "deg" => Ok(number.to_radians()),
+        "turn" => Ok(number * TAU),
+        _ => Err(invalid()),
+    }
+}
+
+/// Constants of the animation in `shader.wgsl`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnimationSettings {
+    /// Angular frequency of the pulsing point size.
+    pub pulse_frequency: f32,
+    /// Phase offset of the pulse per unit of the prime.
+    pub phase_factor: f32,
+    /// Rotation of the whole plot in radians per second.
+    pub rotation_rate: f32,
+    /// A prime `p` is placed at radius `radius_law(p) / radius_divisor`.
+    pub radius_divisor: f32,
+    /// Points shrink by this factor per zoom level when zooming in.
+    pub zoom_base_in: f32,
+    /// Points grow by this factor per zoom level when zooming out.
+    pub zoom_base_out: f32,
+    /// A prime `p` is placed at the angle `angle_multiplier * p` in radians.
+    pub angle_multiplier: f64,
+    /// Change of `angle_multiplier` per second of game time, to sweep through the spirals.
+    pub angle_sweep: f64,
+    /// Factor applied to the size of every point.
+    pub point_scale: f32,
+}
+
+impl AnimationSettings {
+    /// Scale of the points at the given zoom exponent, mirroring `zoom_scale` in `shader.wgsl`.
+    pub fn zoom_scale(&self, zoom: f32) -> f32 {
+        match zoom > 0.0 {
+            true => 1.0 / f32::powf(self.zoom_base_in, zoom),
+            false => 1.0 / f32::powf(self.zoom_base_out, zoom),
+        }
+    }
+
+    /// Blends all constants from `self` at `t = 0` to `other` at `t = 1`.
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            pulse_frequency: f32::lerp(self.pulse_frequency, other.pulse_frequency, t),
+            phase_factor: f32::lerp(self.phase_factor, other.phase_factor, t),
+            rotation_rate: f32::lerp(self.rotation_rate, other.rotation_rate, t),
+            radius_divisor: f32::lerp(self.radius_divisor, other.radius_divisor, t),
+            zoom_base_in: f32::lerp(self.zoom_base_in, other.zoom_base_in, t),
+            zoom_base_out: f32::lerp(self.zoom_base_out, other.zoom_base_out, t),
+            angle_multiplier: f64::lerp(self.angle_multiplier, other.angle_multiplier, t as f64),
+            angle_sweep: f64::lerp(self.angle_sweep, other.angle_sweep, t as f64),
+            point_scale: f32::lerp(self.point_scale, other.point_scale, t),
+        }
+    }
+
+    /// The angle multiplier after `elapsed` seconds of game time, including the sweep.
+    pub fn angle_multiplier_at(&self, elapsed: f64) -> f64 {
+        self.angle_multiplier + self.angle_sweep * elapsed
+    }
+}
+
+impl Default for AnimationSettings {
+    fn default() -> Self {
+        Self {
+            pulse_frequency: 2.0,
+            phase_factor: 0.1,
+            rotation_rate: 0.002,
+            radius_divisor: 512.0,
+            zoom_base_in: 1.5,
+            zoom_base_out: 1.75,
+            angle_multiplier: 1.0,
+            angle_sweep: 0.0,
+            point_scale: 1.0,
+        }
+    }
+}
+
+/// How changes of the layout and the animation are blended in.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TransitionSettings {
+    /// Length of a transition in seconds of game time. Zero switches immediately.
+    pub duration: f32,
+    pub easing: Easing,
+}
+
+impl Default for TransitionSettings {
+    fn default() -> Self {
+        Self {
+            duration: 2.0,
+            easing: Easing::CubicInOut,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    #[default]
+    Linear,
+    Smoothstep,
+    CubicInOut,
+}
+
+impl Easing {
+    /// Maps the linear progress `t` in `0..=1` to the eased progress.
+    pub fn ease(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::Smoothstep => t * t * (3.0 - 2.0 * t),
+            Self::CubicInOut if t < 0.5 => 4.0 * t * t * t,
+            Self::CubicInOut => 1.0 - f32::powi(-2.0 * t + 2.0, 3) / 2.0,
+        }
+    }
+}
+
+impl FromStr for Easing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linear" => Ok(Self::Linear),
+            "smoothstep" => Ok(Self::Smoothstep),
+            "cubic_in_out" => Ok(Self::CubicInOut),
+            _ => Err(format!("invalid easing: {s}")),
+        }
+    }
+}
+
+/// How the points of a layer of primes are drawn.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    Component,
+    ExtractComponent,
+    Serialize,
+    Deserialize,
+)]
+#[serde(default)]
+pub struct PointStyle {
+    pub shape: PointShape,
+    pub size: SizeLaw,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PointShape {
+    /// A polygon, with fewer sides when the points are small on screen.
+    #[default]
+    Circle,
+    Square,
+    Cross,
+    /// A square with a disc cut out in the fragment shader, antialiased at any size.
+    Disc,
+}
+
+impl PointShape {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Circle => Self::Square,
+            Self::Square => Self::Cross,
+            Self::Cross => Self::Disc,
+            Self::Disc => Self::Circle,
+        }
+    }
+}
+
+impl FromStr for PointShape {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "circle" => Ok(Self::Circle),
+            "square" => Ok(Self::Square),
+            "cross" => Ok(Self::Cross),
+            "disc" => Ok(Self::Disc),
+            _ => Err(format!("invalid point shape: {s}")),
+        }
+    }
+}
+
+/// Factor applied to the size of every point, mirroring `size_factor` in `instanced.wgsl`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SizeLaw {
+    /// All points have the same size.
+    #[default]
+    Constant,
+    /// `ln p / 10`, so points grow slowly towards the outside.
+    Log,
+    /// The gap to the previous prime relative to the average gap `ln p`.
+    Gap,
+}
+
+impl SizeLaw {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Constant => Self::Log,
+            Self::Log => Self::Gap,
+            Self::Gap => Self::Constant,
+        }
+    }
+}
+
+impl FromStr for SizeLaw {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "constant" => Ok(Self::Constant),
+            "log" => Ok(Self::Log),
+            "gap" => Ok(Self::Gap),
+            _ => Err(format!("invalid size law: {s}")),
+        }
+    }
+}
+
+/// Bloom of the game camera.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GlowSettings {
+    pub intensity: f32,
+    /// Brightness below which colors don't glow.
+    pub threshold: f32,
+}
+
+impl Default for GlowSettings {
+    fn default() -> Self {
+        Self {
+            intensity: 0.15,
+            threshold: 0.0,
+        }
+    }
+}
+
+/// How the view is controlled.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewSettings {
+    /// Speed of the game time, before the speed-up keys.
+    pub speed: f32,
+    /// Range of the zoom levels the controls zoom to.
+    pub zoom_min: f32,
+    pub zoom_max: f32,
+}
+
+impl Default for ViewSettings {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            zoom_min: -10.0,
+            zoom_max: 40.0,
+        }
+    }
+}
+
+/// Largest speed of the game time. Far larger speeds would overflow the game time within a frame.
+pub const MAX_SPEED: f32 = 65536.0;
+
+/// A speed of the game time as set by the user, limited to `MAX_SPEED`.
+pub fn checked_speed(speed: f64) -> Result<f32, String> {
+    if !speed.is_finite() {
+        Err(format!("the speed must be finite: {speed}"))
+    } else if speed < 0.0 {
+        Err("the speed must not be negative".into())
+    } else {
+        Ok(f64::min(speed, MAX_SPEED as f64) as f32)
+    }
+}
+
+/// Overlays shown on top of the plot.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HudSettings {
+    /// The frame rate and other diagnostics.
+    pub fps: bool,
+    /// What is known about the number gone to.
+    pub info: bool,
+}
+
+impl Default for HudSettings {
+    fn default() -> Self {
+        Self {
+            fps: false,
+            info: true,
+        }
+    }
+}
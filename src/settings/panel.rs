@@ -0,0 +1,576 @@
+use super::{
+    AnimationSettings, GlowSettings, Settings, TransitionSettings, ViewSettings, MAX_SPEED,
+};
+use crate::{AppState, Args};
+use bevy::prelude::*;
+
+pub struct SettingsPanelPlugin;
+
+impl Plugin for SettingsPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::Game),
+            spawn_open_button.run_if(|args: Res<Args>| matches!(*args, Args::Run { .. })),
+        );
+        app.add_systems(
+            Update,
+            (toggle, adjust, choose, preset, update_values)
+                .chain()
+                .run_if(in_state(AppState::Game)),
+        );
+    }
+}
+
+/// A numeric setting that can be adjusted from the panel.
+struct Field {
+    label: &'static str,
+    get: fn(&Settings) -> f32,
+    set: fn(&mut Settings, f32),
+    step: Step,
+    min: f32,
+    max: f32,
+}
+
+enum Step {
+    Add(f32),
+    Mul(f32),
+}
+
+impl Field {
+    fn adjust(&self, settings: &mut Settings, direction: f32) {
+        let value = (self.get)(settings);
+        let value = match self.step {
+            Step::Add(step) => value + direction * step,
+            Step::Mul(step) => value * f32::powf(step, direction),
+        };
+        (self.set)(settings, f32::min(f32::max(self.min, value), self.max));
+    }
+}
+
+/// A setting with a few values, cycled through from the panel.
+struct Choice {
+    label: &'static str,
+    get: fn(&Settings) -> String,
+    next: fn(&mut Settings),
+}
+
+fn on_off(value: bool) -> String {
+    match value {
+        true => "On".into(),
+        false => "Off".into(),
+    }
+}
+
+const CHOICES: &[Choice] = &[
+    Choice {
+        label: "Layout",
+        get: |s| format!("{:?}", s.layout),
+        next: |s| s.layout = s.layout.next(),
+    },
+    Choice {
+        label: "Radius law",
+        get: |s| format!("{:?}", s.radius_law),
+        next: |s| s.radius_law = s.radius_law.next(),
+    },
+    Choice {
+        label: "Render mode",
+        get: |s| format!("{:?}", s.render_mode),
+        next: |s| s.render_mode = s.render_mode.next(),
+    },
+    Choice {
+        label: "Color ramp",
+        get: |s| format!("{:?}", s.density.ramp),
+        next: |s| s.density.ramp = s.density.ramp.next(),
+    },
+    Choice {
+        label: "Point shape",
+        get: |s| format!("{:?}", s.points.shape),
+        next: |s| s.points.shape = s.points.shape.next(),
+    },
+    Choice {
+        label: "Point size",
+        get: |s| format!("{:?}", s.points.size),
+        next: |s| s.points.size = s.points.size.next(),
+    },
+    Choice {
+        label: "FPS",
+        get: |s| on_off(s.hud.fps),
+        next: |s| s.hud.fps = !s.hud.fps,
+    },
+    Choice {
+        label: "Number info",
+        get: |s| on_off(s.hud.info),
+        next: |s| s.hud.info = !s.hud.info,
+    },
+];
+
+const FIELDS: &[Field] = &[
+    Field {
+        label: "Prime bound",
+        get: |s| s.bound as f32,
+        // Saturates at `u32::MAX`, which draws all primes
+        set: |s, v| s.bound = v as u32,
+        step: Step::Mul(2.0),
+        min: 2.0,
+        max: f32::INFINITY,
+    },
+    Field {
+        label: "Point scale",
+        get: |s| s.animation.point_scale,
+        set: |s, v| s.animation.point_scale = v,
+        step: Step::Mul(1.25),
+        min: 0.01,
+        max: f32::INFINITY,
+    },
+    Field {
+        label: "Speed",
+        get: |s| s.view.speed,
+        set: |s, v| s.view.speed = v,
+        step: Step::Mul(2.0),
+        // Above zero, so doubling gets the game going again
+        min: 1.0 / 64.0,
+        max: MAX_SPEED,
+    },
+    Field {
+        label: "Zoom min",
+        get: |s| s.view.zoom_min,
+        set: |s, v| s.view.zoom_min = v,
+        step: Step::Add(1.0),
+        min: f32::NEG_INFINITY,
+        max: f32::INFINITY,
+    },
+    Field {
+        label: "Zoom max",
+        get: |s| s.view.zoom_max,
+        set: |s, v| s.view.zoom_max = v,
+        step: Step::Add(1.0),
+        min: f32::NEG_INFINITY,
+        max: f32::INFINITY,
+    },
+    Field {
+        label: "Pulse frequency",
+        get: |s| s.animation.pulse_frequency,
+        set: |s, v| s.animation.pulse_frequency = v,
+        step: Step::Add(0.25),
+        min: 0.0,
+        max: f32::INFINITY,
+    },
+    Field {
+        label: "Phase factor",
+        get: |s| s.animation.phase_factor,
+        set: |s, v| s.animation.phase_factor = v,
+        step: Step::Mul(2.0),
+        min: 0.0,
+        max: f32::INFINITY,
+    },
+    Field {
+        label: "Rotation rate",
+        get: |s| s.animation.rotation_rate,
+        set: |s, v| s.animation.rotation_rate = v,
+        step: Step::Add(0.0005),
+        min: f32::NEG_INFINITY,
+        max: f32::INFINITY,
+    },
+    Field {
+        label: "Radius divisor",
+        get: |s| s.animation.radius_divisor,
+        set: |s, v| s.animation.radius_divisor = v,
+        step: Step::Mul(2.0),
+        min: 1.0,
+        max: f32::INFINITY,
+    },
+    Field {
+        label: "Zoom base in",
+        get: |s| s.animation.zoom_base_in,
+        set: |s, v| s.animation.zoom_base_in = v,
+        step: Step::Add(0.05),
+        min: 1.0,
+        max: f32::INFINITY,
+    },
+    Field {
+        label: "Zoom base out",
+        get: |s| s.animation.zoom_base_out,
+        set: |s, v| s.animation.zoom_base_out = v,
+        step: Step::Add(0.05),
+        min: 1.0,
+        max: f32::INFINITY,
+    },
+    Field {
+        label: "Angle (deg)",
+        get: |s| s.animation.angle_multiplier.to_degrees() as f32,
+        set: |s, v| s.animation.angle_multiplier = (v as f64).to_radians(),
+        step: Step::Add(0.1),
+        min: f32::NEG_INFINITY,
+        max: f32::INFINITY,
+    },
+    Field {
+        label: "Sweep (deg/s)",
+        get: |s| s.animation.angle_sweep.to_degrees() as f32,
+        set: |s, v| s.animation.angle_sweep = (v as f64).to_radians(),
+        step: Step::Add(0.0001),
+        min: f32::NEG_INFINITY,
+        max: f32::INFINITY,
+    },
+    Field {
+        label: "Transition",
+        get: |s| s.transition.duration,
+        set: |s, v| s.transition.duration = v,
+        step: Step::Add(0.5),
+        min: 0.0,
+        max: f32::INFINITY,
+    },
+    Field {
+        label: "Glow intensity",
+        get: |s| s.glow.intensity,
+        set: |s, v| s.glow.intensity = v,
+        step: Step::Add(0.05),
+        min: 0.0,
+        max: f32::INFINITY,
+    },
+    Field {
+        label: "Glow threshold",
+        get: |s| s.glow.threshold,
+        set: |s, v| s.glow.threshold = v,
+        step: Step::Add(0.1),
+        min: 0.0,
+        max: f32::INFINITY,
+    },
+];
+
+#[derive(Debug, Component)]
+struct SettingsPanel;
+
+#[derive(Debug, Component)]
+struct FieldValue(usize);
+
+#[derive(Debug, Component)]
+struct ChoiceValue(usize);
+
+#[derive(Debug, Component)]
+struct ChoiceButton(usize);
+
+/// Opens or closes the panel, for touch screens without F1.
+#[derive(Debug, Component)]
+struct ToggleButton;
+
+/// Marks the button that opens the panel, hidden while it is open.
+#[derive(Debug, Component)]
+struct OpenButton;
+
+#[derive(Debug, Component)]
+struct AdjustButton {
+    field: usize,
+    direction: f32,
+}
+
+#[derive(Debug, Component)]
+enum PresetButton {
+    #[cfg(not(target_arch = "wasm32"))]
+    Save,
+    Reset,
+}
+
+const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+const BUTTON_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.1);
+const BUTTON_COLOR_HOVERED: Color = Color::srgba(1.0, 1.0, 1.0, 0.25);
+
+#[cfg(not(target_arch = "wasm32"))]
+const PRESET_PATH: &str = "./presets/preset.json";
+
+fn text_style() -> TextStyle {
+    TextStyle {
+        font_size: 16.0,
+        color: TEXT_COLOR,
+        ..default()
+    }
+}
+
+fn spawn_open_button(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(10.0),
+                    right: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            },
+            OpenButton,
+            StateScoped(AppState::Game),
+        ))
+        .with_children(|parent| {
+            spawn_button(parent, "Settings", &text_style(), ToggleButton);
+        });
+}
+
+fn toggle(
+    mut commands: Commands,
+    query: Query<Entity, With<SettingsPanel>>,
+    input: Res<ButtonInput<KeyCode>>,
+    buttons: Query<&Interaction, (Changed<Interaction>, With<ToggleButton>)>,
+    mut open_button: Query<&mut Visibility, With<OpenButton>>,
+) {
+    let pressed = buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+    if input.just_pressed(KeyCode::F1) || pressed {
+        let open = match query.get_single() {
+            Ok(e) => {
+                commands.entity(e).despawn_recursive();
+                false
+            }
+            Err(_) => {
+                spawn_panel(&mut commands);
+                true
+            }
+        };
+        for mut visibility in &mut open_button {
+            *visibility = match open {
+                true => Visibility::Hidden,
+                false => Visibility::Inherited,
+            };
+        }
+    }
+}
+
+/// Spawns a row of the panel, with the label in front of the controls.
+fn spawn_row(
+    parent: &mut ChildBuilder,
+    label: &str,
+    text_style: &TextStyle,
+    controls: impl FnOnce(&mut ChildBuilder),
+) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(6.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(label, text_style.clone()).with_style(Style {
+                    width: Val::Px(140.0),
+                    ..default()
+                }),
+            );
+            controls(parent);
+        });
+}
+
+fn spawn_panel(commands: &mut Commands) {
+    let text_style = text_style();
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(10.0),
+                    right: Val::Px(10.0),
+                    flex_direction: FlexDirection::Column,
+                    // Rows wrap into more columns on small screens
+                    flex_wrap: FlexWrap::Wrap,
+                    max_height: Val::Percent(95.0),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    row_gap: Val::Px(4.0),
+                    column_gap: Val::Px(16.0),
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.7).into(),
+                ..default()
+            },
+            SettingsPanel,
+            StateScoped(AppState::Game),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Settings (F1)",
+                text_style.clone(),
+            ));
+
+            for (idx, choice) in CHOICES.iter().enumerate() {
+                spawn_row(parent, choice.label, &text_style, |parent| {
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    width: Val::Px(124.0),
+                                    height: Val::Px(24.0),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: BUTTON_COLOR.into(),
+                                ..default()
+                            },
+                            ChoiceButton(idx),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle::from_section("", text_style.clone()),
+                                ChoiceValue(idx),
+                            ));
+                        });
+                });
+            }
+
+            for (idx, field) in FIELDS.iter().enumerate() {
+                spawn_row(parent, field.label, &text_style, |parent| {
+                    spawn_button(
+                        parent,
+                        "-",
+                        &text_style,
+                        AdjustButton {
+                            field: idx,
+                            direction: -1.0,
+                        },
+                    );
+                    parent.spawn((
+                        TextBundle::from_section("", text_style.clone()).with_style(Style {
+                            width: Val::Px(90.0),
+                            ..default()
+                        }),
+                        FieldValue(idx),
+                    ));
+                    spawn_button(
+                        parent,
+                        "+",
+                        &text_style,
+                        AdjustButton {
+                            field: idx,
+                            direction: 1.0,
+                        },
+                    );
+                });
+            }
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(6.0),
+                        margin: UiRect::top(Val::Px(4.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    spawn_button(parent, "Save preset", &text_style, PresetButton::Save);
+                    spawn_button(parent, "Reset", &text_style, PresetButton::Reset);
+                    spawn_button(parent, "Close", &text_style, ToggleButton);
+                });
+        });
+}
+
+fn spawn_button(
+    parent: &mut ChildBuilder,
+    label: &str,
+    text_style: &TextStyle,
+    marker: impl Component,
+) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    min_width: Val::Px(24.0),
+                    height: Val::Px(24.0),
+                    padding: UiRect::horizontal(Val::Px(6.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: BUTTON_COLOR.into(),
+                ..default()
+            },
+            marker,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(label, text_style.clone()));
+        });
+}
+
+fn adjust(
+    mut settings: ResMut<Settings>,
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor, Option<&AdjustButton>),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    for (interaction, mut background, button) in &mut buttons {
+        *background = match interaction {
+            Interaction::Hovered | Interaction::Pressed => BUTTON_COLOR_HOVERED,
+            Interaction::None => BUTTON_COLOR,
+        }
+        .into();
+
+        if let (Interaction::Pressed, Some(button)) = (interaction, button) {
+            FIELDS[button.field].adjust(&mut settings, button.direction);
+        }
+    }
+}
+
+fn choose(
+    mut settings: ResMut<Settings>,
+    buttons: Query<(&Interaction, &ChoiceButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction == Interaction::Pressed {
+            (CHOICES[button.0].next)(&mut settings);
+        }
+    }
+}
+
+fn preset(
+    mut settings: ResMut<Settings>,
+    buttons: Query<(&Interaction, &PresetButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match button {
+            #[cfg(not(target_arch = "wasm32"))]
+            PresetButton::Save => match settings.save_preset(PRESET_PATH.as_ref()) {
+                Ok(()) => info!("Saved preset to {}", PRESET_PATH),
+                Err(e) => error!("Failed to save preset: {}", e),
+            },
+            PresetButton::Reset => {
+                settings.animation = AnimationSettings::default();
+                settings.transition = TransitionSettings::default();
+                settings.glow = GlowSettings::default();
+                settings.view = ViewSettings::default();
+            }
+        }
+    }
+}
+
+/// Formats a field value, with decimals only where they matter.
+fn format_value(value: f32) -> String {
+    match value.abs() >= 1000.0 {
+        true => format!("{value:.0}"),
+        false => format!("{value:.4}"),
+    }
+}
+
+fn update_values(
+    settings: Res<Settings>,
+    mut fields: Query<(Ref<FieldValue>, &mut Text), Without<ChoiceValue>>,
+    mut choices: Query<(Ref<ChoiceValue>, &mut Text), Without<FieldValue>>,
+) {
+    for (value, mut text) in &mut fields {
+        if settings.is_changed() || value.is_added() {
+            text.sections[0].value = format_value((FIELDS[value.0].get)(&settings));
+        }
+    }
+    for (value, mut text) in &mut choices {
+        if settings.is_changed() || value.is_added() {
+            text.sections[0].value = (CHOICES[value.0].get)(&settings);
+        }
+    }
+}
@@ -1,18 +1,39 @@
-use crate::{camera::GameCameraBundle, AppState, AssetsState};
-use bevy::prelude::*;
+use crate::{
+    assets::{LoadingProgress, PrimesAsset},
+    camera::GameCameraBundle,
+    AppState, Args, AssetsState,
+};
+use bevy::{asset::AssetLoadFailedEvent, prelude::*};
 
 pub struct SplashScreenPlugin;
 
 impl Plugin for SplashScreenPlugin {
     fn build(&self, app: &mut App) {
+        app.init_resource::<LoadErrors>();
+
         // Setup and cleanup
-        app.add_systems(OnEnter(AppState::SplashScreen), setup);
+        app.add_systems(
+            OnEnter(AppState::SplashScreen),
+            (
+                setup,
+                spawn_error_buttons.run_if(in_state(AssetsState::Error)),
+            )
+                .chain(),
+        );
         app.add_systems(OnExit(AppState::SplashScreen), cleanup);
+        app.add_systems(
+            OnEnter(AssetsState::Error),
+            (
+                spawn_error_buttons.run_if(in_state(AppState::SplashScreen)),
+                leave_game.run_if(in_state(AppState::Game)),
+            ),
+        );
 
         // Update
+        app.add_systems(Update, load_errors);
         app.add_systems(
             Update,
-            splash_screen.run_if(in_state(AppState::SplashScreen)),
+            (splash_screen, progress, error_buttons).run_if(in_state(AppState::SplashScreen)),
         );
     }
 }
@@ -23,21 +44,52 @@ struct SplashScreen {
     clicked: bool,
 }
 
-impl Default for SplashScreen {
-    fn default() -> Self {
+impl SplashScreen {
+    fn new(duration: f32) -> Self {
         Self {
-            // TODO: Non-zero duration
-            timer: Timer::from_seconds(0.0, TimerMode::Once),
+            timer: Timer::from_seconds(duration, TimerMode::Once),
             clicked: false,
         }
     }
 }
 
+/// Errors of loading the primes, kept from the game to the splash screen.
+#[derive(Debug, Default, Resource)]
+struct LoadErrors(Vec<String>);
+
+/// Minimum time the splash screen is shown, unless it is clicked away.
+const SPLASH_SCREEN_SECONDS: f32 = 2.0;
+
+const TITLE_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+const TEXT_COLOR: Color = Color::srgb(0.6, 0.6, 0.6);
+const ERROR_COLOR: Color = Color::srgb(1.0, 0.3, 0.3);
+const BAR_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+const BAR_BACKGROUND_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.1);
+const BUTTON_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.1);
+const BUTTON_COLOR_HOVERED: Color = Color::srgba(1.0, 1.0, 1.0, 0.25);
+
+#[derive(Debug, Component)]
+struct SplashScreenRoot;
+
+#[derive(Debug, Component)]
+struct ProgressBar;
+
+#[derive(Debug, Component)]
+struct StatusText;
+
+#[derive(Debug, Component)]
+enum ErrorButton {
+    Retry,
+    Quit,
+}
+
 fn splash_screen(
     time: Res<Time>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
     mouse_input: Res<ButtonInput<MouseButton>>,
     assets_state: Res<State<AssetsState>>,
+    loading_progress: Res<LoadingProgress>,
+    args: Res<Args>,
     mut splash_screen: ResMut<SplashScreen>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
@@ -45,21 +97,260 @@ fn splash_screen(
     splash_screen.clicked |=
         keyboard_input.just_pressed(KeyCode::Space) || mouse_input.just_pressed(MouseButton::Left);
 
-    // TODO: Handle AssetsState::Error
+    // The remaining primes stream in while the game runs, but screenshots need all of them
+    let ready = match **assets_state {
+        AssetsState::Loaded => true,
+        AssetsState::Loading => {
+            matches!(*args, Args::Run { .. }) && loading_progress.primes_read() > 0
+        }
+        AssetsState::Error => false,
+    };
+    if ready && (splash_screen.timer.finished() || splash_screen.clicked) {
+        next_state.set(AppState::Game);
+    }
+}
 
-    if **assets_state == AssetsState::Loaded
-        && (splash_screen.timer.finished() || splash_screen.clicked)
+fn load_errors(
+    mut events: EventReader<AssetLoadFailedEvent<PrimesAsset>>,
+    mut errors: ResMut<LoadErrors>,
+    args: Res<Args>,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    for event in events.read() {
+        error!("Failed to load {}: {}", event.path, event.error);
+        errors.0.push(event.error.to_string());
+
+        // Nobody is there to retry
+        if let Args::Screenshot { .. } = *args {
+            app_exit.send(AppExit::error());
+        }
+    }
+}
+
+/// Goes back to the splash screen, to show a failure of loading the primes that are streamed into
+/// the game.
+fn leave_game(mut next_state: ResMut<NextState<AppState>>) {
+    next_state.set(AppState::SplashScreen);
+}
+
+fn progress(
+    time: Res<Time>,
+    assets_state: Res<State<AssetsState>>,
+    loading_progress: Res<LoadingProgress>,
+    errors: Res<LoadErrors>,
+    mut bar: Query<(&mut Style, &mut Visibility), With<ProgressBar>>,
+    mut status: Query<(&mut Text, Ref<StatusText>)>,
+) {
+    let (mut bar_style, mut bar_visibility) = bar.single_mut();
+    let (mut text, status_text) = status.single_mut();
+    if !assets_state.is_changed()
+        && !status_text.is_added()
+        && **assets_state != AssetsState::Loading
     {
-        next_state.set(AppState::Game);
+        return;
     }
+
+    let (value, color) = match **assets_state {
+        AssetsState::Loading => {
+            // The size of the primes is not known up front, so the bar sweeps back and forth
+            let t = time.elapsed_seconds() % 2.0;
+            let t = if t < 1.0 { t } else { 2.0 - t };
+            bar_style.left = Val::Percent(75.0 * t);
+            bar_style.width = Val::Percent(25.0);
+            *bar_visibility = Visibility::Inherited;
+
+            let mib = loading_progress.bytes_read() as f64 / (1 << 20) as f64;
+            (format!("Loading primes... {mib:.1} MiB"), TEXT_COLOR)
+        }
+        AssetsState::Loaded => {
+            bar_style.left = Val::Percent(0.0);
+            bar_style.width = Val::Percent(100.0);
+            *bar_visibility = Visibility::Inherited;
+
+            ("Click or press Space to start".to_string(), TEXT_COLOR)
+        }
+        AssetsState::Error => {
+            *bar_visibility = Visibility::Hidden;
+
+            let mut value = "Failed to load the primes".to_string();
+            for error in &errors.0 {
+                value.push('\n');
+                value.push_str(error);
+            }
+            (value, ERROR_COLOR)
+        }
+    };
+    text.sections[0].value = value;
+    text.sections[0].style.color = color;
 }
 
-fn setup(mut commands: Commands) {
-    commands.init_resource::<SplashScreen>();
+fn error_buttons(
+    mut buttons: Query<(&Interaction, &ErrorButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut splash_screen: ResMut<SplashScreen>,
+    mut errors: ResMut<LoadErrors>,
+    mut next_state: ResMut<NextState<AssetsState>>,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    for (interaction, button, mut background) in &mut buttons {
+        *background = match interaction {
+            Interaction::Hovered | Interaction::Pressed => BUTTON_COLOR_HOVERED,
+            Interaction::None => BUTTON_COLOR,
+        }
+        .into();
+
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            ErrorButton::Retry => {
+                errors.0.clear();
+                splash_screen.clicked = false;
+                next_state.set(AssetsState::Loading);
+            }
+            ErrorButton::Quit => {
+                app_exit.send(AppExit::error());
+            }
+        }
+    }
+}
+
+fn setup(mut commands: Commands, args: Res<Args>) {
+    commands.insert_resource(SplashScreen::new(match *args {
+        Args::Run { .. } => SPLASH_SCREEN_SECONDS,
+        Args::Screenshot { .. } => 0.0,
+    }));
     commands.spawn((
         GameCameraBundle::default(),
         StateScoped(AppState::SplashScreen),
     ));
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(16.0),
+                    ..default()
+                },
+                ..default()
+            },
+            SplashScreenRoot,
+            StateScoped(AppState::SplashScreen),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Primes",
+                TextStyle {
+                    font_size: 72.0,
+                    color: TITLE_COLOR,
+                    ..default()
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                "The prime numbers in polar coordinates",
+                TextStyle {
+                    font_size: 20.0,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+            ));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(320.0),
+                        height: Val::Px(4.0),
+                        margin: UiRect::top(Val::Px(24.0)),
+                        overflow: Overflow::clip(),
+                        ..default()
+                    },
+                    background_color: BAR_BACKGROUND_COLOR.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: BAR_COLOR.into(),
+                            ..default()
+                        },
+                        ProgressBar,
+                    ));
+                });
+
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 16.0,
+                        color: TEXT_COLOR,
+                        ..default()
+                    },
+                )
+                .with_text_justify(JustifyText::Center),
+                StatusText,
+            ));
+        });
+}
+
+fn spawn_error_buttons(mut commands: Commands, root: Query<Entity, With<SplashScreenRoot>>) {
+    let Ok(root) = root.get_single() else {
+        return;
+    };
+
+    let text_style = TextStyle {
+        font_size: 16.0,
+        color: TITLE_COLOR,
+        ..default()
+    };
+    commands.entity(root).with_children(|parent| {
+        parent
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(8.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                StateScoped(AssetsState::Error),
+            ))
+            .with_children(|parent| {
+                spawn_button(parent, "Retry", &text_style, ErrorButton::Retry);
+                spawn_button(parent, "Quit", &text_style, ErrorButton::Quit);
+            });
+    });
+}
+
+fn spawn_button(
+    parent: &mut ChildBuilder,
+    label: &str,
+    text_style: &TextStyle,
+    button: ErrorButton,
+) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                    ..default()
+                },
+                background_color: BUTTON_COLOR.into(),
+                ..default()
+            },
+            button,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(label, text_style.clone()));
+        });
 }
 
 fn cleanup(mut commands: Commands) {
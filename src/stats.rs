@@ -0,0 +1,288 @@
+use crate::primes::{Format, Primes};
+use bevy::app::AppExit;
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt, path::Path};
+
+/// Statistics of a set of primes, as reported by the `stats` subcommand.
+#[derive(Debug, Clone, Serialize)]
+pub struct Stats {
+    /// Number of primes, π of the largest one.
+    pub count: usize,
+    pub largest: Option<u32>,
+    pub gaps: GapStats,
+    /// Pairs of primes `(p, p + 2)`.
+    pub twins: usize,
+    /// Pairs of primes `(p, p + 4)`.
+    pub cousins: usize,
+    /// Pairs of primes `(p, p + 6)`.
+    pub sexy: usize,
+    pub residues: Residues,
+    /// π(x) against its approximations, at the powers of ten up to the largest prime.
+    pub comparisons: Vec<Comparison>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GapStats {
+    pub max: u32,
+    pub mean: f64,
+    /// Number of occurrences of every gap.
+    pub histogram: BTreeMap<u32, usize>,
+    /// Gaps larger than all gaps before them.
+    pub records: Vec<Gap>,
+}
+
+/// The gap between `prime` and the prime before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Gap {
+    pub prime: u32,
+    pub gap: u32,
+}
+
+/// Number of primes in every residue class modulo `modulus`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Residues {
+    pub modulus: u32,
+    pub counts: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Comparison {
+    pub x: u32,
+    pub pi: usize,
+    pub li: f64,
+    pub x_over_ln_x: f64,
+}
+
+impl Primes {
+    /// Gaps between all consecutive primes.
+    pub fn gaps(&self) -> impl Iterator<Item = Gap> + '_ {
+        self.primes().windows(2).map(|w| Gap {
+            prime: w[1],
+            gap: w[1] - w[0],
+        })
+    }
+
+    pub fn gap_stats(&self) -> GapStats {
+        let mut stats = GapStats::default();
+        let mut count = 0;
+        let mut sum = 0;
+        for gap in self.gaps() {
+            count += 1;
+            sum += gap.gap as u64;
+            *stats.histogram.entry(gap.gap).or_default() += 1;
+            if gap.gap > stats.max {
+                stats.max = gap.gap;
+                stats.records.push(gap);
+            }
+        }
+        stats.mean = match count {
+            0 => 0.0,
+            _ => sum as f64 / count as f64,
+        };
+        stats
+    }
+
+    /// Number of primes `p` for which `p + distance` is prime too.
+    pub fn pair_count(&self, distance: u32) -> usize {
+        let primes = self.primes();
+        let mut upper = 0;
+        primes
+            .iter()
+            .filter(|p| {
+                let Some(partner) = p.checked_add(distance) else {
+                    return false;
+                };
+                while upper < primes.len() && primes[upper] < partner {
+                    upper += 1;
+                }
+                primes.get(upper) == Some(&partner)
+            })
+            .count()
+    }
+
+    /// Number of primes in every residue class modulo `modulus`, which must not be zero.
+    pub fn residues(&self, modulus: u32) -> Residues {
+        assert!(
+            modulus > 0,
+            "the modulus of residue classes must not be zero"
+        );
+        let mut counts = vec![0; modulus as usize];
+        for p in self.primes() {
+            counts[(p % modulus) as usize] += 1;
+        }
+        Residues { modulus, counts }
+    }
+
+    /// All statistics, with the residue classes modulo `modulus`, which must not be zero.
+    pub fn stats(&self, modulus: u32) -> Stats {
+        let largest = self.primes().last().copied();
+        let comparisons = std::iter::successors(Some(10u32), |x| x.checked_mul(10))
+            .take_while(|x| largest.is_some_and(|largest| *x <= largest))
+            .map(|x| Comparison {
+                x,
+                pi: self.prime_pi(x),
+                li: li(x as f64),
+                x_over_ln_x: x as f64 / f64::ln(x as f64),
+            })
+            .collect();
+
+        Stats {
+            count: self.primes().len(),
+            largest,
+            gaps: self.gap_stats(),
+            twins: self.pair_count(2),
+            cousins: self.pair_count(4),
+            sexy: self.pair_count(6),
+            residues: self.residues(modulus),
+            comparisons,
+        }
+    }
+}
+
+/// The logarithmic integral, by Ramanujan's series.
+pub fn li(x: f64) -> f64 {
+    const EULER_GAMMA: f64 = 0.577_215_664_901_532_9;
+
+    if x <= 1.0 {
+        return f64::NEG_INFINITY;
+    }
+
+    let ln_x = x.ln();
+    let mut sum = 0.0;
+    let mut term = 1.0; // (ln x)^n / (n! 2^(n - 1))
+    let mut inner = 0.0; // sum of 1 / (2k + 1) for k < n / 2
+    for n in 1..200 {
+        term *= ln_x / n as f64 / if n == 1 { 1.0 } else { 2.0 };
+        if (n - 1) % 2 == 0 {
+            inner += 1.0 / (n as f64);
+        }
+        let summand = if n % 2 == 1 { term } else { -term } * inner;
+        sum += summand;
+        if summand.abs() < 1e-17 * sum.abs() {
+            break;
+        }
+    }
+    EULER_GAMMA + ln_x.ln() + x.sqrt() * sum
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Primes:       {}", self.count)?;
+        if let Some(largest) = self.largest {
+            writeln!(f, "Largest:      {}", largest)?;
+        }
+        writeln!(f, "Twin pairs:   {}", self.twins)?;
+        writeln!(f, "Cousin pairs: {}", self.cousins)?;
+        writeln!(f, "Sexy pairs:   {}", self.sexy)?;
+
+        writeln!(f)?;
+        writeln!(f, "Gaps: max {}, mean {:.3}", self.gaps.max, self.gaps.mean)?;
+        writeln!(f, "  Records:")?;
+        for record in &self.gaps.records {
+            writeln!(
+                f,
+                "    {:>4} after {}",
+                record.gap,
+                record.prime - record.gap
+            )?;
+        }
+        writeln!(f, "  Histogram:")?;
+        for (gap, count) in &self.gaps.histogram {
+            writeln!(f, "    {:>4} {}", gap, count)?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Residues mod {}:", self.residues.modulus)?;
+        for (residue, count) in self.residues.counts.iter().enumerate() {
+            if *count > 0 {
+                writeln!(f, "  {:>4} {}", residue, count)?;
+            }
+        }
+
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:>12} {:>12} {:>14} {:>14}",
+            "x", "pi(x)", "li(x)", "x / ln x"
+        )?;
+        for c in &self.comparisons {
+            writeln!(
+                f,
+                "{:>12} {:>12} {:>14.1} {:>14.1}",
+                c.x, c.pi, c.li, c.x_over_ln_x
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Prints the statistics of the primes in the given file or sieved up to `sieve`, as text or JSON.
+pub fn run(path: &Path, sieve: Option<u32>, max: Option<u32>, modulus: u32, json: bool) -> AppExit {
+    if modulus == 0 {
+        eprintln!("The modulus must not be zero");
+        return AppExit::error();
+    }
+
+    let primes = match sieve {
+        Some(sieve) => Primes::build_with_progress(sieve, |done, total| {
+            crate::print_progress("Sieving", done, total)
+        }),
+        None => match std::fs::read(path) {
+            Ok(bytes) => match Primes::decode(&bytes, Format::from_path(path)) {
+                Ok(primes) => primes,
+                Err(e) => {
+                    eprintln!("Failed to read primes from {}: {}", path.display(), e);
+                    return AppExit::error();
+                }
+            },
+            Err(e) => {
+                eprintln!("Failed to read {}: {}", path.display(), e);
+                return AppExit::error();
+            }
+        },
+    };
+    let primes = match max {
+        Some(max) => Primes::from_unchecked(primes.primes()[..primes.prime_pi(max)].to_vec()),
+        None => primes,
+    };
+
+    let stats = primes.stats(modulus);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats).unwrap());
+    } else {
+        print!("{}", stats);
+    }
+
+    AppExit::Success
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn li_matches_known_values() {
+        assert!((li(2.0) - 1.045_163_780_117_492).abs() < 1e-9);
+        assert!((li(1e6) - 78_627.549_159).abs() < 1e-3);
+    }
+
+    #[test]
+    fn stats_below_a_million() {
+        let primes = Primes::build(1_000_000);
+        let stats = primes.stats(44);
+        assert_eq!(stats.count, 78_498);
+        assert_eq!(stats.largest, Some(999_983));
+        assert_eq!(
+            stats.gaps.records.last(),
+            Some(&Gap {
+                prime: 492_227,
+                gap: 114
+            })
+        );
+        assert_eq!(stats.gaps.max, 114);
+        assert_eq!(stats.twins, 8_169);
+        assert_eq!(stats.cousins, 8_144);
+        assert_eq!(stats.sexy, 16_386);
+        assert_eq!(stats.residues.counts.iter().sum::<usize>(), stats.count);
+    }
+}
@@ -0,0 +1,138 @@
+use crate::settings::{Easing, Layout, RadiusLaw, RenderMode};
+use bevy::math::{FloatExt, Vec2};
+use serde::{Deserialize, Serialize};
+
+/// Keyframes of the camera, time and view, played back live or rendered frame by frame.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Timeline {
+    pub keyframes: Vec<Keyframe>,
+}
+
+/// Values of the view at a point of the timeline. Every value forms its own track, so a keyframe
+/// only lists the values that change.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keyframe {
+    /// Seconds since the start of the timeline.
+    pub at: f32,
+    /// Game time in seconds, jumped to at this keyframe.
+    pub time: Option<f64>,
+    /// Speed of the game time relative to the timeline.
+    pub speed: Option<f64>,
+    pub zoom: Option<f32>,
+    /// Center of the view of the game camera.
+    pub pan: Option<Vec2>,
+    pub layout: Option<Layout>,
+    pub radius_law: Option<RadiusLaw>,
+    pub render_mode: Option<RenderMode>,
+    /// Angle of a prime per unit, in radians.
+    pub angle_multiplier: Option<f64>,
+    /// Interpolation of the values from the previous keyframe of each track to this one.
+    pub curve: Easing,
+}
+
+/// Values of all tracks at a point of the timeline, `None` if a track has no keyframes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub speed: Option<f64>,
+    pub zoom: Option<f32>,
+    pub pan: Option<Vec2>,
+    pub layout: Option<Layout>,
+    pub radius_law: Option<RadiusLaw>,
+    pub render_mode: Option<RenderMode>,
+    pub angle_multiplier: Option<f64>,
+}
+
+impl Timeline {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(
+        path: &std::path::Path,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let mut timeline: Self = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        if timeline
+            .keyframes
+            .iter()
+            .any(|keyframe| !keyframe.at.is_finite())
+        {
+            return Err("keyframes must be at finite times".into());
+        }
+        timeline.keyframes.sort_by(|a, b| a.at.total_cmp(&b.at));
+        Ok(timeline)
+    }
+
+    /// Time of the last keyframe.
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.at)
+    }
+
+    pub fn sample(&self, at: f32) -> Sample {
+        Sample {
+            speed: self.track(at, |k| k.speed, |a, b, t| f64::lerp(a, b, t as f64)),
+            zoom: self.track(at, |k| k.zoom, f32::lerp),
+            pan: self.track(at, |k| k.pan, Vec2::lerp),
+            layout: self.track(at, |k| k.layout, |a, _, _| a),
+            radius_law: self.track(at, |k| k.radius_law, |a, _, _| a),
+            render_mode: self.track(at, |k| k.render_mode, |a, _, _| a),
+            angle_multiplier: self.track(
+                at,
+                |k| k.angle_multiplier,
+                |a, b, t| f64::lerp(a, b, t as f64),
+            ),
+        }
+    }
+
+    /// Game time at the start of the timeline.
+    pub fn start_time(&self) -> f64 {
+        self.keyframes
+            .iter()
+            .take_while(|keyframe| keyframe.at <= 0.0)
+            .filter_map(|keyframe| keyframe.time)
+            .last()
+            .unwrap_or(0.0)
+    }
+
+    /// Advances the game time `elapsed` from `from` to `to` on the timeline, at the sampled speed
+    /// and with the jumps of the keyframes in between.
+    pub fn advance(&self, elapsed: f64, from: f32, to: f32) -> f64 {
+        let speed = self.sample(from).speed.unwrap_or(1.0);
+        let jump = self
+            .keyframes
+            .iter()
+            .rev()
+            .filter(|keyframe| from < keyframe.at && keyframe.at <= to)
+            .find_map(|keyframe| Some((keyframe.at, keyframe.time?)));
+        match jump {
+            Some((at, time)) => time + (to - at) as f64 * speed,
+            None => elapsed + (to - from) as f64 * speed,
+        }
+    }
+
+    /// Value of a track at the given time. Values are held before the first and after the last
+    /// keyframe of the track.
+    fn track<T: Copy>(
+        &self,
+        at: f32,
+        value: impl Fn(&Keyframe) -> Option<T>,
+        lerp: impl Fn(T, T, f32) -> T,
+    ) -> Option<T> {
+        let mut previous = None;
+        for keyframe in &self.keyframes {
+            let Some(next) = value(keyframe) else {
+                continue;
+            };
+            if keyframe.at <= at {
+                previous = Some((keyframe.at, next));
+                continue;
+            }
+
+            return match previous {
+                Some((previous_at, previous)) => {
+                    let t = (at - previous_at) / (keyframe.at - previous_at);
+                    Some(lerp(previous, next, keyframe.curve.ease(t)))
+                }
+                None => Some(next),
+            };
+        }
+        previous.map(|(_, value)| value)
+    }
+}